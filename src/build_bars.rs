@@ -0,0 +1,94 @@
+use crate::time_args::TimeArgs;
+
+use cryptory::{BarBuilder, BarKind, Kline, Trade};
+
+use clap::{Args, ValueEnum};
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Build bars from downloaded trades or aggTrades archives.
+#[derive(Debug, Args)]
+pub struct BarsArgs {
+    /// Downloaded trades or aggTrades archives, read in the order they are given
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// How trades are grouped into bars
+    #[arg(long, value_enum)]
+    by: BarType,
+
+    /// Trades, volume or quote volume of a bar.
+    /// For imbalance bars the expected trades of the first bar
+    #[arg(long)]
+    size: f64,
+
+    /// Write the bars to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    time: TimeArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum BarType {
+    /// A bar every SIZE trades
+    Tick,
+    /// A bar every SIZE traded quantity
+    Volume,
+    /// A bar every SIZE traded quote value
+    Dollar,
+    /// Tick imbalance bars
+    Imbalance,
+}
+
+impl BarsArgs {
+    fn kind(&self) -> Result<BarKind, String> {
+        if self.size.is_nan() || self.size <= 0.0 {
+            return Err(format!("invalid size {}, must be above 0", self.size));
+        }
+        Ok(match self.by {
+            BarType::Tick => BarKind::Tick(self.size.ceil() as u64),
+            BarType::Volume => BarKind::Volume(self.size),
+            BarType::Dollar => BarKind::Dollar(self.size),
+            BarType::Imbalance => BarKind::Imbalance(self.size),
+        })
+    }
+}
+
+/// Write the bars as csv in the schema of kline files, including the last unfinished bar.
+pub fn build_bars(args: &BarsArgs) -> Result<(), String> {
+    let mut builder = BarBuilder::new(args.kind()?);
+    let time = args.time.output();
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(
+            std::fs::File::create(path)
+                .map_err(|e| format!("could not create {}: {e}", path.display()))?,
+        ),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut out = std::io::BufWriter::new(out.as_mut());
+
+    let write_error = |e: std::io::Error| format!("could not write bars: {e}");
+    writeln!(out, "{}", Kline::CSV_HEADER).map_err(write_error)?;
+
+    for path in &args.files {
+        let mut result = Ok(());
+        Trade::read(path, |trade| {
+            if let Some(bar) = builder.push(&trade) {
+                if result.is_ok() {
+                    result = writeln!(out, "{}", bar.to_csv_with(&time));
+                }
+            }
+        })
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+        result.map_err(write_error)?;
+    }
+
+    if let Some(bar) = builder.finish() {
+        writeln!(out, "{}", bar.to_csv_with(&time)).map_err(write_error)?;
+    }
+    out.flush().map_err(write_error)
+}
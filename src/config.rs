@@ -0,0 +1,135 @@
+use cryptory::{parse_base_url, parse_rate, ClientOptions};
+
+use reqwest::Url;
+use serde::Deserialize;
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Settings read from the config file.
+/// Command line arguments and environment variables take precedence over these.
+///
+/// Example:
+/// ```toml
+/// base_url = "http://binance-cache.internal/"
+/// mirrors = ["https://data.binance.vision/"]
+/// proxy = "http://proxy.internal:3128"
+/// ca_bundle = "/etc/ssl/certs/internal.pem"
+/// connect_timeout = 10
+/// read_timeout = 30
+/// limit_rate = "2M"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Host to download the files from.
+    pub base_url: Option<String>,
+    /// Hosts tried in order if a file could not be downloaded from the base url.
+    pub mirrors: Vec<String>,
+    pub proxy: Option<String>,
+    /// Pem file with extra root certificates to trust.
+    pub ca_bundle: Option<PathBuf>,
+    /// Connect timeout in seconds.
+    pub connect_timeout: Option<u64>,
+    /// Read timeout in seconds.
+    pub read_timeout: Option<u64>,
+    pub user_agent: Option<String>,
+    /// Max combined download rate in bytes per second, e.g. 500K or 2M.
+    pub limit_rate: Option<String>,
+}
+
+impl Config {
+    /// Load the config from the given path, or from the default location if no path is given.
+    /// A missing file at the default location gives the default config.
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Config::default())
+            }
+            Err(e) => return Err(ConfigError::CouldNotRead(path, e)),
+        };
+
+        toml::from_str(&content).map_err(|e| ConfigError::Invalid(path, e.to_string()))
+    }
+
+    pub fn base_url(&self) -> Result<Option<Url>, ConfigError> {
+        self.base_url
+            .as_deref()
+            .map(parse_base_url)
+            .transpose()
+            .map_err(ConfigError::InvalidUrl)
+    }
+
+    pub fn mirrors(&self) -> Result<Vec<Url>, ConfigError> {
+        self.mirrors
+            .iter()
+            .map(|url| parse_base_url(url).map_err(ConfigError::InvalidUrl))
+            .collect()
+    }
+
+    pub fn limit_rate(&self) -> Result<Option<u64>, ConfigError> {
+        self.limit_rate
+            .as_deref()
+            .map(parse_rate)
+            .transpose()
+            .map_err(ConfigError::InvalidRate)
+    }
+
+    pub fn client_options(&self) -> Result<ClientOptions, ConfigError> {
+        let proxy = self
+            .proxy
+            .as_deref()
+            .map(|proxy| Url::parse(proxy).map_err(|e| ConfigError::InvalidUrl(e.to_string())))
+            .transpose()?;
+
+        Ok(ClientOptions {
+            proxy,
+            ca_bundle: self.ca_bundle.clone(),
+            connect_timeout: self.connect_timeout.map(Duration::from_secs),
+            read_timeout: self.read_timeout.map(Duration::from_secs),
+            user_agent: self.user_agent.clone(),
+        })
+    }
+}
+
+/// $XDG_CONFIG_HOME/cryptory/config.toml, falling back to ~/.config/cryptory/config.toml
+fn default_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("cryptory").join("config.toml"))
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    CouldNotRead(PathBuf, std::io::Error),
+    Invalid(PathBuf, String),
+    InvalidUrl(String),
+    InvalidRate(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::CouldNotRead(path, e) => {
+                write!(f, "Could not read config file {}: {e}", path.display())
+            }
+            ConfigError::Invalid(path, e) => {
+                write!(f, "Invalid config file {}: {e}", path.display())
+            }
+            ConfigError::InvalidUrl(e) => write!(f, "Invalid url in config file: {e}"),
+            ConfigError::InvalidRate(e) => write!(f, "Invalid limit_rate in config file: {e}"),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use super::TimeUnit;
+
+use std::fmt::Display;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Call `f` with the fields of each row of the csv file in a downloaded archive.
+///
+/// The archive is read without extracting it. Some binance files start with a header,
+/// a first row that does not start with a number is skipped.
+pub fn read_rows(
+    path: &Path,
+    mut f: impl FnMut(&[&str]) -> Result<(), String>,
+) -> Result<(), DataError> {
+    let file = std::fs::File::open(path).map_err(DataError::CouldNotOpen)?;
+    let mut archive = zip::ZipArchive::new(file).map_err(DataError::InvalidArchive)?;
+    if archive.is_empty() {
+        return Err(DataError::EmptyArchive);
+    }
+    let csv = archive.by_index(0).map_err(DataError::InvalidArchive)?;
+
+    for (i, line) in BufReader::new(csv).lines().enumerate() {
+        let line = line.map_err(DataError::CouldNotRead)?;
+        if line.is_empty() || (i == 0 && !line.starts_with(|c: char| c.is_ascii_digit())) {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').collect();
+        f(&fields).map_err(|reason| DataError::InvalidRow {
+            line: i + 1,
+            reason,
+        })?;
+    }
+    Ok(())
+}
+
+/// Parse a field of a row, `name` is used in the error.
+pub(crate) fn field<T: std::str::FromStr>(
+    fields: &[&str],
+    index: usize,
+    name: &str,
+) -> Result<T, String> {
+    let value = fields
+        .get(index)
+        .ok_or_else(|| format!("missing {name}, expected atleast {} columns", index + 1))?;
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid {name} {value}"))
+}
+
+/// Parse a timestamp in milliseconds or microseconds, that is in the range of dates.
+pub(crate) fn time_field(fields: &[&str], index: usize, name: &str) -> Result<i64, String> {
+    let time: i64 = field(fields, index, name)?;
+    TimeUnit::of(time)
+        .to_datetime(time)
+        .map_err(|e| format!("invalid {name}: {e}"))?;
+    Ok(time)
+}
+
+/// Binance writes booleans as True/False in spot files and true/false in futures files.
+pub(crate) fn bool_field(fields: &[&str], index: usize, name: &str) -> Result<bool, String> {
+    let value: String = field(fields, index, name)?;
+    match value.to_ascii_lowercase().as_str() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("invalid {name} {value}")),
+    }
+}
+
+/// Errors when reading a downloaded archive.
+#[derive(Debug)]
+pub enum DataError {
+    CouldNotOpen(std::io::Error),
+    InvalidArchive(zip::result::ZipError),
+    /// The archive has no csv file in it.
+    EmptyArchive,
+    CouldNotRead(std::io::Error),
+    /// A row of the csv file could not be parsed, line starts at 1.
+    InvalidRow {
+        line: usize,
+        reason: String,
+    },
+    /// The file name does not tell what kind of data the file holds.
+    UnknownDataType,
+    /// A timestamp is out of the range of dates.
+    TimestampOutOfRange(i64),
+}
+
+impl Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataError::CouldNotOpen(e) => write!(f, "could not open file: {e}"),
+            DataError::InvalidArchive(e) => write!(f, "not a valid zip archive: {e}"),
+            DataError::EmptyArchive => write!(f, "the archive is empty"),
+            DataError::CouldNotRead(e) => write!(f, "could not read archive: {e}"),
+            DataError::InvalidRow { line, reason } => write!(f, "line {line}: {reason}"),
+            DataError::UnknownDataType => {
+                write!(
+                    f,
+                    "unknown kind of data, expected a file name as downloaded by cryptory"
+                )
+            }
+            DataError::TimestampOutOfRange(time) => {
+                write!(f, "timestamp {time} is out of the range of dates")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DataError::CouldNotOpen(e) | DataError::CouldNotRead(e) => Some(e),
+            DataError::InvalidArchive(e) => Some(e),
+            DataError::EmptyArchive
+            | DataError::InvalidRow { .. }
+            | DataError::UnknownDataType
+            | DataError::TimestampOutOfRange(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    /// Write an archive with a csv file holding `content`, or no file when it is None.
+    fn archive(name: &str, content: Option<&str>) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "cryptory-archive-{name}-{}.zip",
+            std::process::id()
+        ));
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        if let Some(content) = content {
+            zip.start_file("rows.csv", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    fn read(name: &str, content: &str) -> Result<Vec<Vec<String>>, DataError> {
+        let path = archive(name, Some(content));
+        let mut rows = Vec::new();
+        let result = read_rows(&path, |fields| {
+            if fields[0] == "bad" {
+                return Err("bad row".to_string());
+            }
+            rows.push(fields.iter().map(|f| f.to_string()).collect());
+            Ok(())
+        });
+        std::fs::remove_file(&path).unwrap();
+        result.map(|()| rows)
+    }
+
+    #[test]
+    fn skip_header_and_empty_lines() {
+        let rows = read("header", "open_time,close\n1,2\n\n3,4\n").unwrap();
+        assert_eq!(vec![vec!["1", "2"], vec!["3", "4"]], rows);
+
+        // only a first row that does not start with a number is a header
+        let rows = read("no-header", "1,2\n").unwrap();
+        assert_eq!(vec![vec!["1", "2"]], rows);
+    }
+
+    #[test]
+    fn report_line_of_invalid_row() {
+        let result = read("invalid-row", "open_time,close\n1,2\nbad,3\n");
+        assert!(matches!(
+            result,
+            Err(DataError::InvalidRow { line: 3, reason }) if reason == "bad row"
+        ));
+    }
+
+    #[test]
+    fn fail_on_empty_or_invalid_archive() {
+        let path = archive("empty", None);
+        let result = read_rows(&path, |_| Ok(()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(DataError::EmptyArchive)));
+
+        let path = std::env::temp_dir().join(format!(
+            "cryptory-archive-invalid-{}.zip",
+            std::process::id()
+        ));
+        std::fs::write(&path, "1,2\n").unwrap();
+        let result = read_rows(&path, |_| Ok(()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(DataError::InvalidArchive(_))));
+
+        let result = read_rows(Path::new("does-not-exist.zip"), |_| Ok(()));
+        assert!(matches!(result, Err(DataError::CouldNotOpen(_))));
+    }
+
+    #[test]
+    fn parse_fields() {
+        let fields = [
+            "1735689600000",
+            " 1.5 ",
+            "True",
+            "false",
+            "x",
+            "9223372036854775807",
+        ];
+
+        assert_eq!(Ok(1.5), field::<f64>(&fields, 1, "close"));
+        assert_eq!(
+            Err("invalid close x".to_string()),
+            field::<f64>(&fields, 4, "close")
+        );
+        assert_eq!(
+            Err("missing count, expected atleast 7 columns".to_string()),
+            field::<u64>(&fields, 6, "count")
+        );
+
+        assert_eq!(Ok(true), bool_field(&fields, 2, "is buyer maker"));
+        assert_eq!(Ok(false), bool_field(&fields, 3, "is buyer maker"));
+        assert_eq!(
+            Err("invalid is buyer maker x".to_string()),
+            bool_field(&fields, 4, "is buyer maker")
+        );
+
+        assert_eq!(Ok(1_735_689_600_000), time_field(&fields, 0, "open time"));
+        assert!(time_field(&fields, 5, "open time").is_err());
+    }
+}
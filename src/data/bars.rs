@@ -0,0 +1,249 @@
+use super::{to_datetime, Kline, TimeUnit, Trade};
+use crate::TimeFrame;
+
+/// How trades are grouped into bars.
+///
+/// A bar closes with the trade that reaches its size, trades are never split between bars.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarKind {
+    /// A bar every n trades.
+    Tick(u64),
+    /// A bar once the traded quantity reaches the size.
+    Volume(f64),
+    /// A bar once the traded quote value (price * quantity) reaches the size.
+    Dollar(f64),
+    /// Tick imbalance bars, a bar closes once the difference between buys and sells
+    /// exceeds what is expected from the previous bars.
+    /// The size is the expected number of trades of the first bar.
+    Imbalance(f64),
+    /// A bar for each period of the timeframe that has trades, like klines.
+    /// A bar closes with the first trade of the next period.
+    Time(TimeFrame),
+}
+
+/// Weight of the latest bar in the expected length and imbalance of imbalance bars.
+const IMBALANCE_ALPHA: f64 = 0.1;
+
+/// Builds bars from trades, in the same schema as klines.
+///
+/// ```
+/// use cryptory::{BarBuilder, BarKind, Trade};
+///
+/// let mut builder = BarBuilder::new(BarKind::Tick(2));
+/// let trade = Trade { time: 1, price: 10.0, qty: 1.0, is_buyer_maker: false, count: 1 };
+/// assert!(builder.push(&trade).is_none());
+/// let bar = builder.push(&trade).unwrap();
+/// assert_eq!(2, bar.trades);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BarBuilder {
+    kind: BarKind,
+    bar: Option<Kline>,
+    /// Trades bought minus sold in the current bar.
+    imbalance: f64,
+    expected_trades: f64,
+    expected_imbalance: f64,
+}
+
+impl BarBuilder {
+    pub fn new(kind: BarKind) -> Self {
+        let expected_trades = match kind {
+            BarKind::Imbalance(expected_trades) => expected_trades,
+            _ => 0.0,
+        };
+        Self {
+            kind,
+            bar: None,
+            imbalance: 0.0,
+            expected_trades,
+            // the first bar closes once all its expected trades are on one side
+            expected_imbalance: 1.0,
+        }
+    }
+
+    /// Add a trade, returns the bar it closes.
+    /// Trades have to be pushed in the order they happened.
+    pub fn push(&mut self, trade: &Trade) -> Option<Kline> {
+        if let BarKind::Time(timeframe) = self.kind {
+            return self.push_time(trade, timeframe);
+        }
+
+        let bar = self.bar.get_or_insert(Kline {
+            open_time: trade.time,
+            ..Kline::default()
+        });
+        add_trade(bar, trade);
+        bar.close_time = trade.time;
+
+        let sign = if trade.is_buy() { 1.0 } else { -1.0 };
+        self.imbalance += sign * trade.count as f64;
+
+        let closed = match self.kind {
+            BarKind::Tick(trades) => bar.trades >= trades,
+            BarKind::Volume(volume) => bar.volume >= volume,
+            BarKind::Dollar(quote_volume) => bar.quote_volume >= quote_volume,
+            BarKind::Imbalance(_) => {
+                let threshold = (self.expected_trades * self.expected_imbalance.abs()).max(1.0);
+                self.imbalance.abs() >= threshold
+            }
+            BarKind::Time(_) => unreachable!("time bars are built in push_time"),
+        };
+
+        if !closed {
+            return None;
+        }
+
+        let bar = self.bar.take().expect("bar was created above");
+        let trades = bar.trades as f64;
+        self.expected_trades += IMBALANCE_ALPHA * (trades - self.expected_trades);
+        self.expected_imbalance +=
+            IMBALANCE_ALPHA * (self.imbalance / trades - self.expected_imbalance);
+        self.imbalance = 0.0;
+        Some(bar)
+    }
+
+    fn push_time(&mut self, trade: &Trade, timeframe: TimeFrame) -> Option<Kline> {
+        let unit = TimeUnit::of(trade.time);
+        let time = to_datetime(trade.time);
+        let open_time = unit.from_datetime(timeframe.align(time));
+
+        let closed = match &self.bar {
+            Some(bar) if bar.open_time != open_time => self.bar.take(),
+            _ => None,
+        };
+
+        let bar = self.bar.get_or_insert(Kline {
+            open_time,
+            // like binance the close time is the last moment of the bar
+            close_time: unit.from_datetime(timeframe.next_open(time)) - 1,
+            ..Kline::default()
+        });
+        add_trade(bar, trade);
+        closed
+    }
+
+    /// The bar that has not reached its size yet, if it has any trades.
+    pub fn finish(self) -> Option<Kline> {
+        self.bar
+    }
+}
+
+fn add_trade(bar: &mut Kline, trade: &Trade) {
+    if bar.trades == 0 {
+        bar.open = trade.price;
+        bar.high = trade.price;
+        bar.low = trade.price;
+    }
+
+    let quote_qty = trade.price * trade.qty;
+    bar.high = bar.high.max(trade.price);
+    bar.low = bar.low.min(trade.price);
+    bar.close = trade.price;
+    bar.volume += trade.qty;
+    bar.quote_volume += quote_qty;
+    bar.trades += trade.count;
+    if trade.is_buy() {
+        bar.taker_buy_volume += trade.qty;
+        bar.taker_buy_quote_volume += quote_qty;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(time: i64, price: f64, qty: f64, is_buyer_maker: bool) -> Trade {
+        Trade {
+            time,
+            price,
+            qty,
+            is_buyer_maker,
+            count: 1,
+        }
+    }
+
+    fn build(kind: BarKind, trades: &[Trade]) -> Vec<Kline> {
+        let mut builder = BarBuilder::new(kind);
+        let mut bars: Vec<Kline> = trades.iter().filter_map(|t| builder.push(t)).collect();
+        bars.extend(builder.finish());
+        bars
+    }
+
+    #[test]
+    fn volume_and_dollar_bars() {
+        let trades = [
+            trade(1, 10.0, 1.0, false),
+            trade(2, 12.0, 2.0, true),
+            trade(3, 9.0, 1.0, false),
+            trade(4, 11.0, 3.0, false),
+            trade(5, 10.0, 1.0, true),
+        ];
+
+        let bars = build(BarKind::Volume(3.0), &trades);
+        assert_eq!(3, bars.len());
+        let first = bars[0];
+        assert_eq!((1, 2), (first.open_time, first.close_time));
+        assert_eq!(
+            (10.0, 12.0, 10.0, 12.0),
+            (first.open, first.high, first.low, first.close)
+        );
+        assert_eq!(
+            (3.0, 34.0, 2),
+            (first.volume, first.quote_volume, first.trades)
+        );
+        assert_eq!(
+            (1.0, 10.0),
+            (first.taker_buy_volume, first.taker_buy_quote_volume)
+        );
+        assert_eq!(4.0, bars[1].volume);
+        // the last bar did not reach its size
+        assert_eq!(1.0, bars[2].volume);
+
+        let bars = build(BarKind::Dollar(40.0), &trades);
+        assert_eq!(
+            vec![43.0, 43.0],
+            bars.iter().map(|b| b.quote_volume).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn imbalance_bars() {
+        // the first bar closes once 3 more trades are bought than sold
+        let trades = [
+            trade(1, 10.0, 1.0, false),
+            trade(2, 10.0, 1.0, true),
+            trade(3, 10.0, 1.0, false),
+            trade(4, 10.0, 1.0, false),
+            trade(5, 10.0, 1.0, false),
+            trade(6, 10.0, 1.0, true),
+        ];
+        let bars = build(BarKind::Imbalance(3.0), &trades);
+        assert_eq!(5, bars[0].trades);
+        assert_eq!(2, bars.len());
+    }
+
+    #[test]
+    fn time_bars() {
+        // 2025-01-01T00:00:00Z in microseconds
+        let start = 1_735_689_600_000_000;
+        let minute = 60_000_000;
+        let trades = [
+            trade(start + 5, 10.0, 1.0, false),
+            trade(start + minute - 1, 11.0, 1.0, true),
+            // no trades in the second minute
+            trade(start + 2 * minute + 7, 12.0, 2.0, false),
+        ];
+
+        let bars = build(BarKind::Time(TimeFrame::M1), &trades);
+        assert_eq!(2, bars.len());
+        assert_eq!(
+            (start, start + minute - 1),
+            (bars[0].open_time, bars[0].close_time)
+        );
+        assert_eq!(
+            (10.0, 11.0, 2),
+            (bars[0].open, bars[0].close, bars[0].trades)
+        );
+        assert_eq!(start + 2 * minute, bars[1].open_time);
+    }
+}
@@ -0,0 +1,184 @@
+use super::archive::{field, read_rows, time_field, DataError};
+use super::to_datetime;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use std::path::Path;
+
+/// Depth of the order book from the mid price up to a percentage away from it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DepthLevel {
+    /// Negative for bids, positive for asks, e.g. -1 is the bids within 1% below the mid price.
+    pub percentage: f64,
+    /// Quantity of all orders up to the percentage.
+    pub depth: f64,
+    /// Quote value of all orders up to the percentage.
+    pub notional: f64,
+}
+
+/// Notional of the bids and asks within a percentage of the mid price.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Liquidity {
+    pub bid: f64,
+    pub ask: f64,
+}
+
+/// The depth profile of the order book at a point in time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DepthSnapshot {
+    /// In milliseconds.
+    pub time: i64,
+    /// Ordered by percentage, from the deepest bids to the deepest asks.
+    pub levels: Vec<DepthLevel>,
+}
+
+impl DepthSnapshot {
+    pub fn level(&self, percentage: f64) -> Option<&DepthLevel> {
+        self.levels
+            .iter()
+            .find(|level| (level.percentage - percentage).abs() < 1e-9)
+    }
+
+    /// Bids within `percentage` below and asks within `percentage` above the mid price,
+    /// None if binance has no level for the percentage.
+    pub fn liquidity(&self, percentage: f64) -> Option<Liquidity> {
+        let percentage = percentage.abs();
+        Some(Liquidity {
+            bid: self.level(-percentage)?.notional,
+            ask: self.level(percentage)?.notional,
+        })
+    }
+}
+
+/// Snapshots of the order book depth from futures bookDepth files, ordered by time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookDepth {
+    snapshots: Vec<DepthSnapshot>,
+}
+
+impl BookDepth {
+    /// Parse a row of a bookDepth file: timestamp, percentage, depth, notional.
+    /// The timestamp is a UTC date and time like 2025-01-01 00:00:08, returned in milliseconds.
+    pub fn from_fields(fields: &[&str]) -> Result<(i64, DepthLevel), String> {
+        let timestamp: String = field(fields, 0, "timestamp")?;
+        let time = match NaiveDateTime::parse_from_str(&timestamp, "%Y-%m-%d %H:%M:%S") {
+            Ok(time) => time.and_utc().timestamp_millis(),
+            Err(_) => {
+                let time = time_field(fields, 0, "timestamp")?;
+                to_datetime(time).timestamp_millis()
+            }
+        };
+        let level = DepthLevel {
+            percentage: field(fields, 1, "percentage")?,
+            depth: field(fields, 2, "depth")?,
+            notional: field(fields, 3, "notional")?,
+        };
+        Ok((time, level))
+    }
+
+    /// Read all snapshots of a downloaded bookDepth archive.
+    pub fn read(path: &Path) -> Result<BookDepth, DataError> {
+        let mut rows = Vec::new();
+        read_rows(path, |fields| {
+            rows.push(BookDepth::from_fields(fields)?);
+            Ok(())
+        })?;
+        Ok(BookDepth::from_rows(rows))
+    }
+
+    /// Group levels with the same time into snapshots.
+    pub fn from_rows(rows: impl IntoIterator<Item = (i64, DepthLevel)>) -> BookDepth {
+        let mut book = BookDepth::default();
+        for (time, level) in rows {
+            book.push(time, level);
+        }
+        book.sort();
+        book
+    }
+
+    /// Add the snapshots of another file, e.g. of the next day.
+    pub fn extend(&mut self, other: BookDepth) {
+        for snapshot in other.snapshots {
+            for level in snapshot.levels {
+                self.push(snapshot.time, level);
+            }
+        }
+        self.sort();
+    }
+
+    pub fn snapshots(&self) -> &[DepthSnapshot] {
+        &self.snapshots
+    }
+
+    /// The latest snapshot at or before `time`, in milliseconds or microseconds.
+    pub fn at(&self, time: i64) -> Option<&DepthSnapshot> {
+        let time = to_datetime(time).timestamp_millis();
+        let published = self
+            .snapshots
+            .partition_point(|snapshot| snapshot.time <= time);
+        self.snapshots.get(published.checked_sub(1)?)
+    }
+
+    fn push(&mut self, time: i64, level: DepthLevel) {
+        // the levels of a snapshot are on consecutive rows
+        match self.snapshots.last_mut().filter(|s| s.time == time) {
+            Some(snapshot) => snapshot.levels.push(level),
+            None => self.snapshots.push(DepthSnapshot {
+                time,
+                levels: vec![level],
+            }),
+        }
+    }
+
+    fn sort(&mut self) {
+        self.snapshots.sort_by_key(|snapshot| snapshot.time);
+        for snapshot in &mut self.snapshots {
+            snapshot
+                .levels
+                .sort_by(|a, b| a.percentage.total_cmp(&b.percentage));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_depth() {
+        let rows = [
+            "2025-01-01 00:00:08,1.00,10,1000",
+            "2025-01-01 00:00:08,-1.00,8,790",
+            "2025-01-01 00:00:08,2.00,30,3100",
+            "2025-01-01 00:00:38,-1.00,9,880",
+            "2025-01-01 00:00:38,1.00,11,1100",
+        ];
+        let book = BookDepth::from_rows(rows.iter().map(|row| {
+            let fields: Vec<&str> = row.split(',').collect();
+            BookDepth::from_fields(&fields).unwrap()
+        }));
+
+        // 2025-01-01T00:00:08Z in milliseconds
+        let first = 1_735_689_608_000;
+        assert_eq!(2, book.snapshots().len());
+        assert_eq!(None, book.at(first - 1));
+
+        let snapshot = book.at(first + 29_999).unwrap();
+        assert_eq!(first, snapshot.time);
+        assert_eq!(-1.0, snapshot.levels[0].percentage);
+        assert_eq!(
+            Some(Liquidity {
+                bid: 790.0,
+                ask: 1000.0
+            }),
+            snapshot.liquidity(1.0)
+        );
+        // no bids within 2%
+        assert_eq!(None, snapshot.liquidity(2.0));
+
+        // in microseconds
+        let snapshot = book.at((first + 30_000) * 1000).unwrap();
+        assert_eq!(Some(880.0), snapshot.liquidity(-1.0).map(|l| l.bid));
+    }
+}
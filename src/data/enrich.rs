@@ -0,0 +1,166 @@
+use super::{to_datetime, FundingRate, Kline, TimeOutput};
+
+use serde::Serialize;
+
+use std::collections::HashMap;
+
+/// A futures kline with the funding rate and the mark and index price of the same bar.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EnrichedKline {
+    pub kline: Kline,
+    /// The latest funding event at or before the close of the kline,
+    /// None when the next funding event was due before the close but was not read.
+    pub funding: Option<FundingRate>,
+    /// Close of the mark price kline with the same open time.
+    pub mark_close: Option<f64>,
+    /// Close of the index price kline with the same open time.
+    pub index_close: Option<f64>,
+}
+
+impl EnrichedKline {
+    /// Header of enriched kline csv files, the kline columns followed by the joined columns.
+    pub const CSV_HEADER: &'static str = "open_time,open,high,low,close,volume,close_time,quote_volume,count,taker_buy_volume,taker_buy_quote_volume,ignore,funding_time,funding_rate,mark_close,index_close";
+
+    /// The enriched kline as a csv row matching [`EnrichedKline::CSV_HEADER`], without a line break.
+    /// Values that could not be joined are left empty.
+    pub fn to_csv(&self) -> String {
+        self.to_csv_with(&TimeOutput::default())
+    }
+
+    /// Same as [`EnrichedKline::to_csv`], with times written as in `time`.
+    pub fn to_csv_with(&self, time: &TimeOutput) -> String {
+        let mut row = self.kline.to_csv_with(time);
+        match self.funding {
+            Some(funding) => row.push_str(&format!(
+                ",{},{:.8}",
+                time.format(funding.time),
+                funding.rate
+            )),
+            None => row.push_str(",,"),
+        }
+        for close in [self.mark_close, self.index_close] {
+            match close {
+                Some(close) => row.push_str(&format!(",{close:.8}")),
+                None => row.push(','),
+            }
+        }
+        row
+    }
+}
+
+/// Attach the most recent funding rate and the mark and index close to each kline.
+///
+/// A funding rate is only attached until the next funding event is due, so klines after
+/// the last event read, e.g. in the current month whose file is not published yet,
+/// have no funding rate instead of a stale one.
+///
+/// Mark and index klines are joined on open time, so they should have the timeframe of the klines.
+/// Times are compared in milliseconds, since binance files use both.
+pub fn enrich(
+    klines: &[Kline],
+    funding_rates: &[FundingRate],
+    mark: &[Kline],
+    index: &[Kline],
+) -> Vec<EnrichedKline> {
+    let mut funding_rates = funding_rates.to_vec();
+    funding_rates.sort_by_key(|funding| millis(funding.time));
+    let closes = |klines: &[Kline]| -> HashMap<i64, f64> {
+        klines
+            .iter()
+            .map(|kline| (millis(kline.open_time), kline.close))
+            .collect()
+    };
+    let mark = closes(mark);
+    let index = closes(index);
+
+    klines
+        .iter()
+        .map(|kline| {
+            let open_time = millis(kline.open_time);
+            let close_time = millis(kline.close_time);
+            let published =
+                funding_rates.partition_point(|funding| millis(funding.time) <= close_time);
+            let funding = published
+                .checked_sub(1)
+                .map(|i| funding_rates[i])
+                .filter(|funding| close_time < millis(funding.time) + interval_millis(funding));
+            EnrichedKline {
+                kline: *kline,
+                funding,
+                mark_close: mark.get(&open_time).copied(),
+                index_close: index.get(&open_time).copied(),
+            }
+        })
+        .collect()
+}
+
+fn interval_millis(funding: &FundingRate) -> i64 {
+    i64::from(funding.interval_hours) * 3_600_000
+}
+
+fn millis(ts: i64) -> i64 {
+    to_datetime(ts).timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines::{kline_at, HOUR, START};
+
+    fn kline(hour: i64, close: f64) -> Kline {
+        Kline {
+            close,
+            ..kline_at(hour)
+        }
+    }
+
+    fn funding(hour: i64, rate: f64) -> FundingRate {
+        FundingRate {
+            time: START + hour * HOUR,
+            interval_hours: 8,
+            rate,
+        }
+    }
+
+    #[test]
+    fn join_funding_mark_and_index() {
+        let klines = [
+            kline(-1, 1.0),
+            kline(0, 1.0),
+            kline(7, 1.0),
+            kline(8, 1.0),
+            kline(15, 1.0),
+            kline(16, 1.0),
+        ];
+        let funding_rates = [funding(8, 0.0002), funding(0, 0.0001)];
+        let mark = [kline(0, 2.0), kline(8, 2.5)];
+        let index = [kline(8, 3.0)];
+
+        let enriched = enrich(&klines, &funding_rates, &mark, &index);
+        let rates: Vec<Option<f64>> = enriched
+            .iter()
+            .map(|e| e.funding.map(|funding| funding.rate))
+            .collect();
+        // the event at hour 16 was not read, so the rate of hour 8 is not carried past it
+        assert_eq!(
+            vec![
+                None,
+                Some(0.0001),
+                Some(0.0001),
+                Some(0.0002),
+                Some(0.0002),
+                None
+            ],
+            rates
+        );
+        assert_eq!(Some(2.0), enriched[1].mark_close);
+        assert_eq!(None, enriched[2].mark_close);
+        assert_eq!(Some(3.0), enriched[3].index_close);
+
+        assert!(enriched[0].to_csv().ends_with(",0,,,,"));
+        assert!(enriched[3].to_csv().ends_with(&format!(
+            ",0,{},0.00020000,2.50000000,3.00000000",
+            START + 8 * HOUR
+        )));
+    }
+}
@@ -0,0 +1,35 @@
+use super::archive::{field, read_rows, time_field, DataError};
+
+use serde::Serialize;
+
+use std::path::Path;
+
+/// A funding event from a futures fundingRate file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FundingRate {
+    /// Time the rate was calculated in milliseconds.
+    pub time: i64,
+    pub interval_hours: u32,
+    pub rate: f64,
+}
+
+impl FundingRate {
+    /// Parse a row of a fundingRate file: calc time, funding interval hours, last funding rate.
+    pub fn from_fields(fields: &[&str]) -> Result<FundingRate, String> {
+        Ok(FundingRate {
+            time: time_field(fields, 0, "calc time")?,
+            interval_hours: field(fields, 1, "funding interval hours")?,
+            rate: field(fields, 2, "last funding rate")?,
+        })
+    }
+
+    /// Read all funding events of a downloaded fundingRate archive.
+    pub fn read(path: &Path) -> Result<Vec<FundingRate>, DataError> {
+        let mut rates = Vec::new();
+        read_rows(path, |fields| {
+            rates.push(FundingRate::from_fields(fields)?);
+            Ok(())
+        })?;
+        Ok(rates)
+    }
+}
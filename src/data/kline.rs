@@ -0,0 +1,80 @@
+use super::archive::{field, read_rows, time_field, DataError};
+use super::TimeOutput;
+
+use serde::Serialize;
+
+use std::path::Path;
+
+/// A bar in the schema of binance kline files.
+///
+/// Times are kept in the unit of the file they come from,
+/// binance switched spot files from milliseconds to microseconds in 2025.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Kline {
+    pub open_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub close_time: i64,
+    pub quote_volume: f64,
+    pub trades: u64,
+    pub taker_buy_volume: f64,
+    pub taker_buy_quote_volume: f64,
+}
+
+impl Kline {
+    /// Header of kline csv files, same as binance uses for futures files.
+    pub const CSV_HEADER: &'static str = "open_time,open,high,low,close,volume,close_time,quote_volume,count,taker_buy_volume,taker_buy_quote_volume,ignore";
+
+    pub fn from_fields(fields: &[&str]) -> Result<Kline, String> {
+        Ok(Kline {
+            open_time: time_field(fields, 0, "open time")?,
+            open: field(fields, 1, "open")?,
+            high: field(fields, 2, "high")?,
+            low: field(fields, 3, "low")?,
+            close: field(fields, 4, "close")?,
+            volume: field(fields, 5, "volume")?,
+            close_time: time_field(fields, 6, "close time")?,
+            quote_volume: field(fields, 7, "quote volume")?,
+            trades: field(fields, 8, "count")?,
+            taker_buy_volume: field(fields, 9, "taker buy volume")?,
+            taker_buy_quote_volume: field(fields, 10, "taker buy quote volume")?,
+        })
+    }
+
+    /// Read all klines of a downloaded kline archive.
+    pub fn read(path: &Path) -> Result<Vec<Kline>, DataError> {
+        let mut klines = Vec::new();
+        read_rows(path, |fields| {
+            klines.push(Kline::from_fields(fields)?);
+            Ok(())
+        })?;
+        Ok(klines)
+    }
+
+    /// The kline as a csv row matching [`Kline::CSV_HEADER`], without a line break.
+    /// Prices and volumes have 8 decimals like binance files.
+    pub fn to_csv(&self) -> String {
+        self.to_csv_with(&TimeOutput::default())
+    }
+
+    /// Same as [`Kline::to_csv`], with the open and close time written as in `time`.
+    pub fn to_csv_with(&self, time: &TimeOutput) -> String {
+        format!(
+            "{},{:.8},{:.8},{:.8},{:.8},{:.8},{},{:.8},{},{:.8},{:.8},0",
+            time.format(self.open_time),
+            self.open,
+            self.high,
+            self.low,
+            self.close,
+            self.volume,
+            time.format(self.close_time),
+            self.quote_volume,
+            self.trades,
+            self.taker_buy_volume,
+            self.taker_buy_quote_volume,
+        )
+    }
+}
@@ -0,0 +1,25 @@
+mod archive;
+mod bars;
+mod depth;
+mod enrich;
+mod funding;
+mod kline;
+mod panel;
+mod reconcile;
+mod stats;
+#[cfg(test)]
+pub(crate) mod test_klines;
+mod time;
+mod trade;
+
+pub use archive::{read_rows, DataError};
+pub use bars::{BarBuilder, BarKind};
+pub use depth::{BookDepth, DepthLevel, DepthSnapshot, Liquidity};
+pub use enrich::{enrich, EnrichedKline};
+pub use funding::FundingRate;
+pub use kline::Kline;
+pub use panel::{Fill, Panel, PanelValue};
+pub use reconcile::{reconcile, FieldDiff, Mismatch};
+pub use stats::{Distribution, Gap, KlineStats};
+pub use time::{to_datetime, TimeFormat, TimeOutput, TimeUnit};
+pub use trade::Trade;
@@ -0,0 +1,158 @@
+use super::{to_datetime, Kline, TimeOutput};
+
+use clap::ValueEnum;
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// How bars missing for a symbol are filled in a [`Panel`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Fill {
+    /// Leave the close and volume empty
+    #[default]
+    Null,
+    /// Repeat the last close with a volume of 0, bars before the first close stay empty
+    Forward,
+}
+
+/// Close and volume of a symbol in a row of a [`Panel`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanelValue {
+    pub close: f64,
+    pub volume: f64,
+}
+
+/// Wide table of klines of several symbols joined on open time,
+/// with a row for each open time any of the symbols has.
+#[derive(Debug, Clone, Default)]
+pub struct Panel {
+    pub symbols: Vec<String>,
+    /// Open time in milliseconds, and a value for each symbol in the order of `symbols`.
+    pub rows: Vec<(i64, Vec<Option<PanelValue>>)>,
+}
+
+impl Panel {
+    /// Join the klines of each symbol on open time.
+    /// Open times are converted to milliseconds, since binance files use both.
+    pub fn new(series: Vec<(String, Vec<Kline>)>, fill: Fill) -> Panel {
+        let mut rows: BTreeMap<i64, Vec<Option<PanelValue>>> = BTreeMap::new();
+        let symbol_count = series.len();
+
+        for (i, (_, klines)) in series.iter().enumerate() {
+            for kline in klines {
+                let open_time = to_datetime(kline.open_time);
+                let row = rows
+                    .entry(open_time.timestamp_millis())
+                    .or_insert_with(|| vec![None; symbol_count]);
+                row[i] = Some(PanelValue {
+                    close: kline.close,
+                    volume: kline.volume,
+                });
+            }
+        }
+
+        let mut rows: Vec<_> = rows.into_iter().collect();
+        if fill == Fill::Forward {
+            let mut last: Vec<Option<PanelValue>> = vec![None; symbol_count];
+            for (_, values) in &mut rows {
+                for (value, last) in values.iter_mut().zip(&mut last) {
+                    match value {
+                        Some(value) => *last = Some(*value),
+                        None => {
+                            *value = last.map(|last| PanelValue {
+                                close: last.close,
+                                volume: 0.0,
+                            })
+                        }
+                    }
+                }
+            }
+        }
+
+        Panel {
+            symbols: series.into_iter().map(|(symbol, _)| symbol).collect(),
+            rows,
+        }
+    }
+
+    /// Write the panel as csv, with a close and volume column for each symbol.
+    pub fn write_csv(&self, out: impl Write) -> std::io::Result<()> {
+        self.write_csv_with(out, &TimeOutput::default())
+    }
+
+    /// Same as [`Panel::write_csv`], with the open times written as in `time`.
+    pub fn write_csv_with(&self, mut out: impl Write, time: &TimeOutput) -> std::io::Result<()> {
+        write!(out, "open_time")?;
+        for symbol in &self.symbols {
+            write!(out, ",{symbol}_close,{symbol}_volume")?;
+        }
+        writeln!(out)?;
+
+        for (open_time, values) in &self.rows {
+            write!(out, "{}", time.format(*open_time))?;
+            for value in values {
+                match value {
+                    Some(value) => write!(out, ",{:.8},{:.8}", value.close, value.volume)?,
+                    None => write!(out, ",,")?,
+                }
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kline(open_time: i64, close: f64) -> Kline {
+        Kline {
+            open_time,
+            close,
+            volume: 1.0,
+            ..Kline::default()
+        }
+    }
+
+    #[test]
+    fn join_and_fill() {
+        // 2025-01-01T00:00:00Z in milliseconds
+        let start = 1_735_689_600_000;
+        let series = vec![
+            (
+                "A".to_string(),
+                vec![kline(start, 1.0), kline(start + 2000, 3.0)],
+            ),
+            // in microseconds
+            ("B".to_string(), vec![kline((start + 1000) * 1000, 20.0)]),
+        ];
+
+        let panel = Panel::new(series.clone(), Fill::Null);
+        let open_times: Vec<i64> = panel.rows.iter().map(|(t, _)| *t).collect();
+        assert_eq!(vec![start, start + 1000, start + 2000], open_times);
+        assert_eq!(None, panel.rows[1].1[0]);
+        assert_eq!(None, panel.rows[0].1[1]);
+
+        let panel = Panel::new(series, Fill::Forward);
+        let filled = PanelValue {
+            close: 1.0,
+            volume: 0.0,
+        };
+        assert_eq!(Some(filled), panel.rows[1].1[0]);
+        // B had not started trading yet
+        assert_eq!(None, panel.rows[0].1[1]);
+
+        let mut csv = Vec::new();
+        panel.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            "open_time,A_close,A_volume,B_close,B_volume",
+            csv.lines().next().unwrap()
+        );
+        assert_eq!(
+            format!("{start},1.00000000,1.00000000,,"),
+            csv.lines().nth(1).unwrap()
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use super::Kline;
+
+use std::collections::BTreeMap;
+
+/// A bar where the klines from binance and the klines rebuilt from trades disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    /// There were trades in the bar, but binance has no kline for it.
+    MissingKline { rebuilt: Kline },
+    /// Binance has a kline with trades, but there are no trades in the bar.
+    MissingTrades { kline: Kline },
+    Different {
+        kline: Kline,
+        rebuilt: Kline,
+        fields: Vec<FieldDiff>,
+    },
+}
+
+impl Mismatch {
+    pub fn open_time(&self) -> i64 {
+        match self {
+            Mismatch::MissingKline { rebuilt } => rebuilt.open_time,
+            Mismatch::MissingTrades { kline } => kline.open_time,
+            Mismatch::Different { kline, .. } => kline.open_time,
+        }
+    }
+}
+
+/// A field that differs between a kline and the kline rebuilt from trades.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub kline: f64,
+    pub rebuilt: f64,
+}
+
+impl FieldDiff {
+    /// Rebuilt minus kline.
+    pub fn diff(&self) -> f64 {
+        self.rebuilt - self.kline
+    }
+
+    /// Difference relative to the kline, infinite when the kline is 0.
+    pub fn relative_diff(&self) -> f64 {
+        self.diff() / self.kline.abs()
+    }
+}
+
+/// Compare klines with klines rebuilt from the trades of the same period, see [`BarKind::Time`](crate::BarKind::Time).
+///
+/// Fields differing by more than `tolerance` relative to the kline are reported,
+/// the sums of volumes are never exact so it should be above 0.
+/// Klines without trades are expected to have no rebuilt kline.
+pub fn reconcile(klines: &[Kline], rebuilt: &[Kline], tolerance: f64) -> Vec<Mismatch> {
+    let mut rebuilt: BTreeMap<i64, &Kline> = rebuilt.iter().map(|k| (k.open_time, k)).collect();
+    let mut mismatches = Vec::new();
+
+    for kline in klines {
+        match rebuilt.remove(&kline.open_time) {
+            Some(rebuilt) => {
+                let fields = diff_fields(kline, rebuilt, tolerance);
+                if !fields.is_empty() {
+                    mismatches.push(Mismatch::Different {
+                        kline: *kline,
+                        rebuilt: *rebuilt,
+                        fields,
+                    });
+                }
+            }
+            None if kline.trades > 0 => mismatches.push(Mismatch::MissingTrades { kline: *kline }),
+            None => (),
+        }
+    }
+
+    mismatches.extend(
+        rebuilt
+            .into_values()
+            .map(|rebuilt| Mismatch::MissingKline { rebuilt: *rebuilt }),
+    );
+    mismatches.sort_by_key(Mismatch::open_time);
+    mismatches
+}
+
+fn diff_fields(kline: &Kline, rebuilt: &Kline, tolerance: f64) -> Vec<FieldDiff> {
+    let fields = [
+        ("open", kline.open, rebuilt.open),
+        ("high", kline.high, rebuilt.high),
+        ("low", kline.low, rebuilt.low),
+        ("close", kline.close, rebuilt.close),
+        ("volume", kline.volume, rebuilt.volume),
+        ("quote_volume", kline.quote_volume, rebuilt.quote_volume),
+        ("count", kline.trades as f64, rebuilt.trades as f64),
+        (
+            "taker_buy_volume",
+            kline.taker_buy_volume,
+            rebuilt.taker_buy_volume,
+        ),
+        (
+            "taker_buy_quote_volume",
+            kline.taker_buy_quote_volume,
+            rebuilt.taker_buy_quote_volume,
+        ),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, kline, rebuilt)| (rebuilt - kline).abs() > tolerance * kline.abs())
+        .map(|(field, kline, rebuilt)| FieldDiff {
+            field,
+            kline,
+            rebuilt,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kline(open_time: i64, close: f64, trades: u64) -> Kline {
+        Kline {
+            open_time,
+            close,
+            trades,
+            ..Kline::default()
+        }
+    }
+
+    #[test]
+    fn find_mismatches() {
+        let klines = [
+            kline(0, 10.0, 1),
+            kline(1, 10.0, 0),
+            kline(2, 10.0, 1),
+            kline(3, 10.0, 2),
+        ];
+        let rebuilt = [kline(0, 10.0, 1), kline(3, 10.5, 2), kline(4, 10.0, 1)];
+
+        let mismatches = reconcile(&klines, &rebuilt, 1e-9);
+        let open_times: Vec<i64> = mismatches.iter().map(Mismatch::open_time).collect();
+        assert_eq!(vec![2, 3, 4], open_times);
+
+        let Mismatch::Different { fields, .. } = &mismatches[1] else {
+            panic!("expected different fields");
+        };
+        assert_eq!("close", fields[0].field);
+        assert_eq!(0.05, fields[0].relative_diff());
+    }
+}
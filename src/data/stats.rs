@@ -0,0 +1,208 @@
+use super::{to_datetime, Kline};
+use crate::TimeFrame;
+
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+
+use std::collections::BTreeMap;
+
+/// Summary of the klines of a ticker and timeframe, to sanity check a dataset before using it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KlineStats {
+    pub first_open: Option<DateTime<Utc>>,
+    pub last_open: Option<DateTime<Utc>>,
+    /// Days with atleast one kline.
+    pub days: usize,
+    /// Klines with a unique open time.
+    pub rows: usize,
+    /// Klines with the same open time as an earlier kline, e.g. from both a monthly and a daily file.
+    pub duplicates: usize,
+    /// Bars between the first and last open time without a kline.
+    pub missing_bars: u64,
+    /// Runs of missing bars, in order of time.
+    pub gaps: Vec<Gap>,
+    /// Returns from the last close of a day to the last close of the next day.
+    pub daily_returns: Option<Distribution>,
+    pub average_volume: f64,
+    pub average_quote_volume: f64,
+    pub average_trades: f64,
+}
+
+/// Bars missing after each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gap {
+    /// Open time of the first missing bar.
+    pub from: DateTime<Utc>,
+    pub bars: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distribution {
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub p5: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    /// None when there are no values.
+    pub fn new(mut values: Vec<f64>) -> Option<Distribution> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+        // nearest rank
+        let percentile = |p: f64| values[((p * count as f64).ceil() as usize).clamp(1, count) - 1];
+
+        Some(Distribution {
+            count,
+            mean,
+            std_dev: variance.sqrt(),
+            min: values[0],
+            p5: percentile(0.05),
+            median: percentile(0.5),
+            p95: percentile(0.95),
+            max: values[count - 1],
+        })
+    }
+}
+
+impl KlineStats {
+    /// Klines can be in any order and in milliseconds or microseconds.
+    pub fn new(klines: &[Kline], timeframe: TimeFrame) -> KlineStats {
+        let mut by_open_time: BTreeMap<DateTime<Utc>, &Kline> = BTreeMap::new();
+        for kline in klines {
+            by_open_time
+                .entry(to_datetime(kline.open_time))
+                .or_insert(kline);
+        }
+        let rows = by_open_time.len();
+
+        let mut gaps = Vec::new();
+        let mut open_times = by_open_time.keys();
+        if let Some(mut previous) = open_times.next().copied() {
+            for &open_time in open_times {
+                let from = timeframe.next_open(previous);
+                let bars = bars_between(timeframe, from, open_time);
+                if bars > 0 {
+                    gaps.push(Gap { from, bars });
+                }
+                previous = open_time;
+            }
+        }
+
+        // last close of each day
+        let mut closes: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+        for (open_time, kline) in &by_open_time {
+            closes.insert(open_time.date_naive(), kline.close);
+        }
+        let returns = closes
+            .iter()
+            .zip(closes.iter().skip(1))
+            .filter(|((day, _), (next_day, _))| **next_day - **day == TimeDelta::days(1))
+            .map(|((_, close), (_, next_close))| next_close / close - 1.0)
+            .collect();
+
+        let average = |value: fn(&Kline) -> f64| match rows {
+            0 => 0.0,
+            _ => by_open_time.values().map(|k| value(k)).sum::<f64>() / rows as f64,
+        };
+
+        KlineStats {
+            first_open: by_open_time.keys().next().copied(),
+            last_open: by_open_time.keys().next_back().copied(),
+            days: closes.len(),
+            rows,
+            duplicates: klines.len() - rows,
+            missing_bars: gaps.iter().map(|gap| gap.bars).sum(),
+            gaps,
+            daily_returns: Distribution::new(returns),
+            average_volume: average(|k| k.volume),
+            average_quote_volume: average(|k| k.quote_volume),
+            average_trades: average(|k| k.trades as f64),
+        }
+    }
+}
+
+/// Number of bars opening from `from` up to but not including `to`.
+fn bars_between(timeframe: TimeFrame, from: DateTime<Utc>, to: DateTime<Utc>) -> u64 {
+    match timeframe.duration() {
+        Some(duration) if to > from => {
+            let bars = (to - from).num_milliseconds() / duration.num_milliseconds();
+            bars as u64
+        }
+        Some(_) => 0,
+        None => {
+            let mut bars = 0;
+            let mut open = from;
+            while open < to {
+                bars += 1;
+                open = timeframe.next_open(open);
+            }
+            bars
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines::{kline_at, HOUR, START};
+
+    fn kline(hour: i64, close: f64) -> Kline {
+        Kline {
+            close,
+            volume: 2.0,
+            trades: 4,
+            ..kline_at(hour)
+        }
+    }
+
+    #[test]
+    fn summarize_klines() {
+        let klines = [
+            kline(0, 100.0),
+            kline(23, 100.0),
+            // missing 2 bars
+            kline(26, 110.0),
+            kline(47, 110.0),
+            kline(47, 110.0),
+            // missing a whole day
+            kline(72, 121.0),
+        ];
+
+        let stats = KlineStats::new(&klines, TimeFrame::H1);
+        assert_eq!(to_datetime(START), stats.first_open.unwrap());
+        assert_eq!(3, stats.days);
+        assert_eq!(5, stats.rows);
+        assert_eq!(1, stats.duplicates);
+        assert_eq!(22 + 2 + 20 + 24, stats.missing_bars);
+        assert_eq!(to_datetime(START + 24 * HOUR), stats.gaps[1].from);
+        assert_eq!(2, stats.gaps[1].bars);
+        assert_eq!(2.0, stats.average_volume);
+        assert_eq!(4.0, stats.average_trades);
+
+        // no return over the missing day
+        let returns = stats.daily_returns.unwrap();
+        assert_eq!(1, returns.count);
+        assert!((returns.mean - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn percentiles() {
+        let values = (1..=100).map(f64::from).collect();
+        let distribution = Distribution::new(values).unwrap();
+        assert_eq!(5.0, distribution.p5);
+        assert_eq!(50.0, distribution.median);
+        assert_eq!(95.0, distribution.p95);
+        assert_eq!(100.0, distribution.max);
+        assert_eq!(None, Distribution::new(Vec::new()));
+    }
+}
@@ -0,0 +1,16 @@
+//! Klines for tests, shared by the library and the commands of the binary.
+
+use crate::Kline;
+
+/// 2025-01-01T00:00:00Z in milliseconds.
+pub const START: i64 = 1_735_689_600_000;
+pub const HOUR: i64 = 3_600_000;
+
+/// An hourly kline opening `hour` hours after [`START`].
+pub fn kline_at(hour: i64) -> Kline {
+    Kline {
+        open_time: START + hour * HOUR,
+        close_time: START + (hour + 1) * HOUR - 1,
+        ..Kline::default()
+    }
+}
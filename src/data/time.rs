@@ -0,0 +1,121 @@
+use super::DataError;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+
+/// Unit of the timestamps in a file.
+/// Binance switched spot files from milliseconds to microseconds in 2025, futures files use milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Millis,
+    Micros,
+}
+
+impl TimeUnit {
+    /// Guess the unit from the size of a timestamp,
+    /// in milliseconds anything this large would be thousands of years from now.
+    pub fn of(timestamp: i64) -> TimeUnit {
+        if timestamp.unsigned_abs() >= 100_000_000_000_000 {
+            TimeUnit::Micros
+        } else {
+            TimeUnit::Millis
+        }
+    }
+
+    /// Fails when the timestamp is out of the range of dates, e.g. in a malformed file.
+    pub fn to_datetime(&self, timestamp: i64) -> Result<DateTime<Utc>, DataError> {
+        match self {
+            TimeUnit::Millis => DateTime::from_timestamp_millis(timestamp),
+            TimeUnit::Micros => DateTime::from_timestamp_micros(timestamp),
+        }
+        .ok_or(DataError::TimestampOutOfRange(timestamp))
+    }
+
+    pub fn from_datetime(&self, datetime: DateTime<Utc>) -> i64 {
+        match self {
+            TimeUnit::Millis => datetime.timestamp_millis(),
+            TimeUnit::Micros => datetime.timestamp_micros(),
+        }
+    }
+}
+
+/// Convert a timestamp in milliseconds or microseconds, see [`TimeUnit::of`].
+/// Timestamps of rows read with [`read_rows`](super::read_rows) are checked to be in range,
+/// others out of the range of dates are clamped to it.
+pub fn to_datetime(timestamp: i64) -> DateTime<Utc> {
+    TimeUnit::of(timestamp)
+        .to_datetime(timestamp)
+        .unwrap_or(match timestamp < 0 {
+            true => DateTime::<Utc>::MIN_UTC,
+            false => DateTime::<Utc>::MAX_UTC,
+        })
+}
+
+/// How timestamps are written in exports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TimeFormat {
+    /// Milliseconds or microseconds since 1970 as in the downloaded files
+    #[default]
+    Epoch,
+    /// ISO-8601 in the timezone of the export, e.g. 2025-01-01T08:00:00+08:00
+    Iso,
+}
+
+/// Format and timezone of the timestamps in an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeOutput {
+    pub format: TimeFormat,
+    /// Only used for ISO-8601, epochs are always in UTC.
+    pub timezone: Tz,
+}
+
+impl TimeOutput {
+    pub fn new(format: TimeFormat, timezone: Tz) -> TimeOutput {
+        TimeOutput { format, timezone }
+    }
+
+    /// Write a timestamp in milliseconds or microseconds, see [`TimeUnit::of`].
+    /// Fractions of a second are only written when there are any, e.g. for close times.
+    pub fn format(&self, timestamp: i64) -> String {
+        match self.format {
+            TimeFormat::Epoch => timestamp.to_string(),
+            TimeFormat::Iso => to_datetime(timestamp)
+                .with_timezone(&self.timezone)
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_timestamps() {
+        // 2025-01-01T00:00:00Z
+        let millis = 1_735_689_600_000;
+        let epoch = TimeOutput::default();
+        assert_eq!("1735689600000", epoch.format(millis));
+
+        let utc = TimeOutput::new(TimeFormat::Iso, Tz::UTC);
+        assert_eq!("2025-01-01T00:00:00Z", utc.format(millis));
+        assert_eq!(
+            "2025-01-01T00:59:59.999999Z",
+            utc.format(millis * 1000 + 3_599_999_999)
+        );
+
+        let tokyo = TimeOutput::new(TimeFormat::Iso, Tz::Asia__Tokyo);
+        assert_eq!("2025-01-01T09:00:00+09:00", tokyo.format(millis));
+    }
+
+    #[test]
+    fn timestamp_out_of_range() {
+        assert!(matches!(
+            TimeUnit::Micros.to_datetime(i64::MAX),
+            Err(DataError::TimestampOutOfRange(i64::MAX))
+        ));
+        assert_eq!(DateTime::<Utc>::MAX_UTC, to_datetime(i64::MAX));
+        assert_eq!(DateTime::<Utc>::MIN_UTC, to_datetime(i64::MIN));
+    }
+}
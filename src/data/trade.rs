@@ -0,0 +1,71 @@
+use super::archive::{bool_field, field, read_rows, time_field, DataError};
+use crate::DataType;
+
+use serde::Serialize;
+
+use std::path::Path;
+
+/// A trade from a trades or aggTrades file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Trade {
+    /// In the unit of the file, milliseconds or microseconds.
+    pub time: i64,
+    pub price: f64,
+    pub qty: f64,
+    /// The seller was the taker, so the trade was a sell.
+    pub is_buyer_maker: bool,
+    /// Number of trades, more than one for aggregated trades.
+    pub count: u64,
+}
+
+impl Trade {
+    /// Parse a row of a trades file: id, price, qty, quote qty, time, is buyer maker.
+    pub fn from_trade_fields(fields: &[&str]) -> Result<Trade, String> {
+        Ok(Trade {
+            price: field(fields, 1, "price")?,
+            qty: field(fields, 2, "qty")?,
+            time: time_field(fields, 4, "time")?,
+            is_buyer_maker: bool_field(fields, 5, "is buyer maker")?,
+            count: 1,
+        })
+    }
+
+    /// Parse a row of an aggTrades file:
+    /// id, price, qty, first trade id, last trade id, time, is buyer maker.
+    pub fn from_agg_trade_fields(fields: &[&str]) -> Result<Trade, String> {
+        let first_trade_id: u64 = field(fields, 3, "first trade id")?;
+        let last_trade_id: u64 = field(fields, 4, "last trade id")?;
+        Ok(Trade {
+            price: field(fields, 1, "price")?,
+            qty: field(fields, 2, "quantity")?,
+            time: time_field(fields, 5, "time")?,
+            is_buyer_maker: bool_field(fields, 6, "is buyer maker")?,
+            count: last_trade_id.saturating_sub(first_trade_id) + 1,
+        })
+    }
+
+    /// Call `f` with each trade of a downloaded trades or aggTrades archive.
+    /// The kind of file is taken from its name, see [`DataType::from_file_name`].
+    pub fn read(path: &Path, mut f: impl FnMut(Trade)) -> Result<(), DataError> {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+
+        let parse = match DataType::from_file_name(file_name) {
+            Some(DataType::Trades) => Trade::from_trade_fields,
+            Some(DataType::AggTrades) => Trade::from_agg_trade_fields,
+            _ => return Err(DataError::UnknownDataType),
+        };
+
+        read_rows(path, |fields| {
+            f(parse(fields)?);
+            Ok(())
+        })
+    }
+
+    /// The taker bought.
+    pub fn is_buy(&self) -> bool {
+        !self.is_buyer_maker
+    }
+}
@@ -0,0 +1,193 @@
+use crate::downloaded::{read_downloaded, skipped};
+use crate::ticker_args::{read_required_tickers, TickerArg};
+use crate::time_args::TimeArgs;
+
+use cryptory::{
+    parse_start_date, to_datetime, BookDepth, DataType, FileInfoIterator, Market, Period,
+    PeriodName, Ticker, TimeOutput,
+};
+
+use chrono::{DateTime, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use clap::Args;
+
+use std::io::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Query and export the order book depth of downloaded futures bookDepth files.
+#[derive(Debug, Args)]
+pub struct DepthArgs {
+    /// Tickers to query, use @FILE or - to read them from a file or stdin
+    #[arg(value_name = "TICKER", required = true)]
+    tickers: Vec<TickerArg>,
+
+    /// Futures market the bookDepth files were downloaded from
+    #[arg(long, value_enum, default_value_t = Market::FuturesUm)]
+    market: Market,
+
+    /// Print the depth profile at this time instead of exporting liquidity,
+    /// e.g. 2025-01-01T12:00:00 in the timezone of --timezone
+    #[arg(long, value_parser = parse_time, allow_hyphen_values = true)]
+    at: Option<NaiveDateTime>,
+
+    /// Percentages from the mid price to export the bid and ask liquidity of
+    #[arg(long, value_delimiter = ',', default_values_t = [1.0, 2.0])]
+    levels: Vec<f64>,
+
+    /// Directory with the downloaded files
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Write the liquidity to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    time: TimeArgs,
+
+    /// Period of the files to export, its days are in the timezone of --timezone
+    #[command(subcommand)]
+    period: Option<Period>,
+}
+
+/// A time like 2025-01-01T12:00:00 or 2025-01-01 12:00, or a date for midnight.
+fn parse_time(input: &str) -> Result<NaiveDateTime, String> {
+    let input = input.trim();
+    for format in [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(input, format) {
+            return Ok(time);
+        }
+    }
+    parse_start_date(input).map(|date| date.and_time(chrono::NaiveTime::MIN))
+}
+
+/// Print the depth profile of each ticker at `--at`, or write a csv with the notional
+/// of the bids and asks within each of `--levels` percent of the mid price for every snapshot.
+/// Files that were not downloaded are skipped.
+pub fn run_depth(args: &DepthArgs) -> Result<(), String> {
+    if !args.market.is_futures() {
+        return Err("bookDepth is only published for futures markets, use --market".to_string());
+    }
+    let tickers = read_required_tickers(&args.tickers)?;
+
+    match (args.at, &args.period) {
+        (Some(at), None) => {
+            let at = args
+                .time
+                .timezone
+                .from_local_datetime(&at)
+                .earliest()
+                .ok_or_else(|| format!("{at} does not exist in {}", args.time.timezone))?
+                .with_timezone(&Utc);
+            // the snapshot just after midnight is in the file of the day before
+            let date = at.date_naive();
+            let period = Period::new(date - TimeDelta::days(1), Some(date), PeriodName::Daily);
+            for ticker in tickers {
+                let book = read_book_depth(args, &ticker, period.clone())?;
+                print_profile(&ticker, &book, at, &args.time.output());
+            }
+            Ok(())
+        }
+        (None, Some(period)) => {
+            if !DataType::BookDepth.is_published(period.period_name()) {
+                return Err(format!(
+                    "no {} {} files are published, use a daily period",
+                    period.period_name(),
+                    DataType::BookDepth
+                ));
+            }
+            period.check_order().map_err(|e| e.to_string())?;
+            let mut rows = Vec::new();
+            let files = period.in_timezone(args.time.timezone);
+            for ticker in tickers {
+                let book = read_book_depth(args, &ticker, files.clone())?;
+                rows.push((ticker, book));
+            }
+            let range = period.time_range(args.time.timezone);
+            let time = args.time.output();
+            let result = match &args.output {
+                Some(path) => std::fs::File::create(path).and_then(|file| {
+                    let out = std::io::BufWriter::new(file);
+                    write_liquidity(&rows, &args.levels, &range, &time, out)
+                }),
+                None => {
+                    let out = std::io::BufWriter::new(std::io::stdout().lock());
+                    write_liquidity(&rows, &args.levels, &range, &time, out)
+                }
+            };
+            result.map_err(|e| format!("could not write liquidity: {e}"))
+        }
+        (Some(_), Some(_)) => Err("--at can not be used together with a period".to_string()),
+        (None, None) => Err("you must provide a period or a time with --at".to_string()),
+    }
+}
+
+fn read_book_depth(args: &DepthArgs, ticker: &Ticker, period: Period) -> Result<BookDepth, String> {
+    let files = FileInfoIterator::new(vec![ticker.clone()], vec![], period)
+        .with_market(args.market)
+        .with_data_type(DataType::BookDepth)
+        .with_output_dir(args.dir.clone());
+    let mut book = BookDepth::default();
+    for file_book in read_downloaded(files, BookDepth::read, skipped)? {
+        book.extend(file_book);
+    }
+    Ok(book)
+}
+
+fn print_profile(ticker: &Ticker, book: &BookDepth, at: DateTime<Utc>, time: &TimeOutput) {
+    let at_ms = at.timestamp_millis();
+    let at = at.with_timezone(&time.timezone);
+    let Some(snapshot) = book.at(at_ms) else {
+        println!("{ticker}: no snapshot at or before {at}");
+        return;
+    };
+
+    println!(
+        "{ticker} at {at}, snapshot of {}",
+        to_datetime(snapshot.time).with_timezone(&time.timezone)
+    );
+    println!("{:>10} {:>20} {:>20}", "percentage", "depth", "notional");
+    for level in &snapshot.levels {
+        println!(
+            "{:>10.2} {:>20.8} {:>20.8}",
+            level.percentage, level.depth, level.notional
+        );
+    }
+}
+
+fn write_liquidity(
+    rows: &[(Ticker, BookDepth)],
+    levels: &[f64],
+    range: &Range<DateTime<Utc>>,
+    time: &TimeOutput,
+    mut out: impl Write,
+) -> std::io::Result<()> {
+    write!(out, "symbol,time")?;
+    for level in levels {
+        write!(out, ",bid_{level},ask_{level}")?;
+    }
+    writeln!(out)?;
+
+    for (ticker, book) in rows {
+        let snapshots = book
+            .snapshots()
+            .iter()
+            .filter(|snapshot| range.contains(&to_datetime(snapshot.time)));
+        for snapshot in snapshots {
+            write!(out, "{ticker},{}", time.format(snapshot.time))?;
+            for level in levels {
+                match snapshot.liquidity(*level) {
+                    Some(liquidity) => write!(out, ",{:.8},{:.8}", liquidity.bid, liquidity.ask)?,
+                    None => write!(out, ",,")?,
+                }
+            }
+            writeln!(out)?;
+        }
+    }
+    out.flush()
+}
@@ -0,0 +1,29 @@
+use cryptory::FileInfo;
+
+use std::fmt::Display;
+use std::path::Path;
+
+/// Read each of the files that were downloaded, in order.
+/// Files that were not downloaded are passed to `missing` and skipped.
+pub fn read_downloaded<T, E: Display>(
+    files: impl IntoIterator<Item = FileInfo>,
+    read: impl Fn(&Path) -> Result<T, E>,
+    mut missing: impl FnMut(&Path),
+) -> Result<Vec<T>, String> {
+    let mut contents = Vec::new();
+    for file in files {
+        if !file.file_path.exists() {
+            missing(&file.file_path);
+            continue;
+        }
+        let content =
+            read(&file.file_path).map_err(|e| format!("{}: {e}", file.file_path.display()))?;
+        contents.push(content);
+    }
+    Ok(contents)
+}
+
+/// Tell the user a file was not downloaded.
+pub fn skipped(path: &Path) {
+    eprintln!("{} not found, skipped", path.display());
+}
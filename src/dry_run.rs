@@ -0,0 +1,147 @@
+use cryptory::FileInfo;
+
+use futures_util::StreamExt;
+use indicatif::DecimalBytes;
+use serde::Serialize;
+
+/// Number of HEAD requests we allow in flight when estimating sizes.
+const CONCURRENT_HEAD_REQUESTS: usize = 16;
+
+/// What would happen to a single file if the download was started.
+#[derive(Debug, Serialize)]
+struct PlannedFile {
+    file_id: usize,
+    source_url: String,
+    file_path: String,
+    exists: bool,
+    /// The file exists and would not be overwritten, so it is not downloaded.
+    skipped: bool,
+    /// Size reported by the host, None if the HEAD request failed
+    /// or the host did not report a size.
+    size: Option<u64>,
+}
+
+/// Walk the files that would be downloaded and print them without writing anything.
+/// The size of each file is estimated with a HEAD request to the host.
+/// Files that exist are skipped unless `overwrite` is set, like a download does.
+pub async fn dry_run(
+    fileinfo_iter: impl IntoIterator<Item = FileInfo>,
+    client: reqwest::Client,
+    overwrite: bool,
+    json: bool,
+) {
+    let mut planned = futures_util::stream::iter(fileinfo_iter)
+        .map(|fileinfo| plan_file(fileinfo, &client, overwrite))
+        .buffered(CONCURRENT_HEAD_REQUESTS);
+
+    let mut files = 0;
+    let mut existing = 0;
+    let mut unknown_size = 0;
+    let mut total_size = 0;
+
+    while let Some(file) = planned.next().await {
+        if file.exists {
+            existing += 1;
+        }
+        if !file.skipped {
+            files += 1;
+            match file.size {
+                Some(size) => total_size += size,
+                None => unknown_size += 1,
+            }
+        }
+
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string(&file).expect("PlannedFile is valid json")
+            );
+            continue;
+        }
+
+        if file.skipped {
+            println!(
+                "{} -> {} [exists, skipped]",
+                file.source_url, file.file_path
+            );
+            continue;
+        }
+        let size = file
+            .size
+            .map(|s| DecimalBytes(s).to_string())
+            .unwrap_or_else(|| "unknown size".to_string());
+        let exists = if file.exists {
+            " [exists, overwritten]"
+        } else {
+            ""
+        };
+        println!("{} -> {} ({size}){exists}", file.source_url, file.file_path);
+    }
+
+    if json {
+        return;
+    }
+
+    if overwrite {
+        println!("\n{files} file(s) would be downloaded, {existing} of them would be overwritten.");
+    } else {
+        println!(
+            "\n{files} file(s) would be downloaded, {existing} already exist and would be skipped, -o to overwrite."
+        );
+    }
+    print!("Estimated total size: {}", DecimalBytes(total_size));
+    if unknown_size > 0 {
+        print!(" ({unknown_size} file(s) with unknown size)");
+    }
+    println!();
+}
+
+async fn plan_file(fileinfo: FileInfo, client: &reqwest::Client, overwrite: bool) -> PlannedFile {
+    let exists = tokio::fs::try_exists(&fileinfo.file_path)
+        .await
+        .unwrap_or(false);
+    let skipped = exists && !overwrite;
+
+    let (source_url, size) = match skipped {
+        true => (&fileinfo.source_url, None),
+        false => head_request(&fileinfo, client).await,
+    };
+
+    PlannedFile {
+        file_id: fileinfo.file_id,
+        source_url: source_url.to_string(),
+        file_path: fileinfo.file_path.display().to_string(),
+        exists,
+        skipped,
+        size,
+    }
+}
+
+/// Ask each url of the file in order for its size, until one has the file.
+/// Returns the url the file would be downloaded from, the source url if none has it.
+async fn head_request<'a>(
+    fileinfo: &'a FileInfo,
+    client: &reqwest::Client,
+) -> (&'a reqwest::Url, Option<u64>) {
+    for url in fileinfo.urls() {
+        match client.head(url.clone()).send().await {
+            Ok(response) if response.status().is_success() => {
+                return (url, content_length(&response))
+            }
+            _ => continue,
+        }
+    }
+    (&fileinfo.source_url, None)
+}
+
+/// reqwest reports a content length of 0 for HEAD responses,
+/// so we read the header ourselves.
+fn content_length(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
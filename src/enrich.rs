@@ -0,0 +1,131 @@
+use crate::downloaded::{read_downloaded, skipped};
+use crate::ticker_args::{read_required_tickers, TickerArg};
+use crate::time_args::TimeArgs;
+
+use cryptory::{
+    enrich, to_datetime, DataType, EnrichedKline, FileInfoIterator, FundingRate, Kline, Market,
+    Period, PeriodName, Ticker, TimeFrame, TimeOutput,
+};
+
+use chrono::Datelike;
+use clap::Args;
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Join funding rates and mark and index prices onto the downloaded futures klines.
+#[derive(Debug, Args)]
+pub struct EnrichArgs {
+    /// Tickers to enrich, use @FILE or - to read them from a file or stdin
+    #[arg(value_name = "TICKER", required = true)]
+    tickers: Vec<TickerArg>,
+
+    /// Timeframe of the klines
+    #[arg(short, long)]
+    timeframe: TimeFrame,
+
+    /// Futures market the klines were downloaded from
+    #[arg(long, value_enum, default_value_t = Market::FuturesUm)]
+    market: Market,
+
+    /// Directory with the downloaded files
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Write the klines to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    time: TimeArgs,
+
+    /// Period of the klines to enrich, its days are in the timezone of --timezone
+    #[command(subcommand)]
+    period: Period,
+}
+
+/// Write a csv with the klines of each ticker, the latest funding rate and the mark and index close.
+/// Files that were not downloaded are skipped and leave their columns empty.
+/// Funding rates are published monthly, klines after the last funding event read have no rate.
+pub fn export_enriched(args: &EnrichArgs) -> Result<(), String> {
+    if !args.market.is_futures() {
+        return Err(
+            "funding rates and mark prices are only published for futures markets, use --market"
+                .to_string(),
+        );
+    }
+    let tickers = read_required_tickers(&args.tickers)?;
+    args.period.check_order().map_err(|e| e.to_string())?;
+
+    let range = args.period.time_range(args.time.timezone);
+    let mut rows = Vec::new();
+    for ticker in tickers {
+        let mut klines = read_klines(args, &ticker, DataType::Klines)?;
+        klines.retain(|kline| range.contains(&to_datetime(kline.open_time)));
+        let mark = read_klines(args, &ticker, DataType::MarkPriceKlines)?;
+        let index = read_klines(args, &ticker, DataType::IndexPriceKlines)?;
+        let funding_rates = read_funding_rates(args, &ticker)?;
+        let enriched = enrich(&klines, &funding_rates, &mark, &index);
+        let without_funding = enriched.iter().filter(|e| e.funding.is_none()).count();
+        if without_funding > 0 {
+            eprintln!(
+                "NOTE: {without_funding} klines of {ticker} have no funding rate, the fundingRate file of their month may not be downloaded or published yet"
+            );
+        }
+        rows.extend(enriched.into_iter().map(|kline| (ticker.clone(), kline)));
+    }
+
+    let time = args.time.output();
+    let result = match &args.output {
+        Some(path) => std::fs::File::create(path)
+            .and_then(|file| write_csv(&rows, &time, std::io::BufWriter::new(file))),
+        None => write_csv(
+            &rows,
+            &time,
+            std::io::BufWriter::new(std::io::stdout().lock()),
+        ),
+    };
+    result.map_err(|e| format!("could not write klines: {e}"))
+}
+
+fn read_klines(
+    args: &EnrichArgs,
+    ticker: &Ticker,
+    data_type: DataType,
+) -> Result<Vec<Kline>, String> {
+    let period = args.period.in_timezone(args.time.timezone);
+    let files = FileInfoIterator::new(vec![ticker.clone()], vec![args.timeframe], period)
+        .with_market(args.market)
+        .with_data_type(data_type)
+        .with_output_dir(args.dir.clone());
+    let klines = read_downloaded(files, Kline::read, skipped)?;
+    Ok(klines.into_iter().flatten().collect())
+}
+
+/// Funding rates are only published monthly, so the months the period covers are read,
+/// including the month before so the first klines have a rate.
+fn read_funding_rates(args: &EnrichArgs, ticker: &Ticker) -> Result<Vec<FundingRate>, String> {
+    let period = args.period.in_timezone(args.time.timezone);
+    let first_month = period.start_date().with_day(1).expect("1st is valid date");
+    let start_date = first_month.pred_opt().unwrap_or(first_month);
+    let end_date = period.end_date().unwrap_or(period.start_date());
+    let months = Period::new(start_date, Some(end_date), PeriodName::Monthly);
+    let files = FileInfoIterator::new(vec![ticker.clone()], vec![], months)
+        .with_market(args.market)
+        .with_data_type(DataType::FundingRate)
+        .with_output_dir(args.dir.clone());
+    let rates = read_downloaded(files, FundingRate::read, skipped)?;
+    Ok(rates.into_iter().flatten().collect())
+}
+
+fn write_csv(
+    rows: &[(Ticker, EnrichedKline)],
+    time: &TimeOutput,
+    mut out: impl Write,
+) -> std::io::Result<()> {
+    writeln!(out, "symbol,{}", EnrichedKline::CSV_HEADER)?;
+    for (ticker, kline) in rows {
+        writeln!(out, "{ticker},{}", kline.to_csv_with(time))?;
+    }
+    out.flush()
+}
@@ -0,0 +1,102 @@
+use cryptory::{Msg, MsgType, Reporter};
+
+use serde::Serialize;
+
+use std::collections::HashMap;
+
+/// Machine readable version of [`MsgType`], printed as one json object per line.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Starting {
+        file_id: usize,
+        file: &'a str,
+        total_size: Option<u64>,
+    },
+    Progress {
+        file_id: usize,
+        file: &'a str,
+        bytes: u64,
+    },
+    Done {
+        file_id: usize,
+        file: &'a str,
+    },
+    Error {
+        file_id: usize,
+        file: &'a str,
+        reason: String,
+    },
+    /// Files that already exist and were not overwritten are skipped, not failed.
+    Summary {
+        total: usize,
+        failed: usize,
+        skipped: usize,
+    },
+}
+
+impl Event<'_> {
+    pub fn print(&self) {
+        println!(
+            "{}",
+            serde_json::to_string(self).expect("Event is valid json")
+        );
+    }
+}
+
+/// Keeps track of the file names so every event can name the file it belongs to,
+/// not only the starting event.
+#[derive(Default)]
+pub struct JsonEvents {
+    names: HashMap<usize, String>,
+}
+
+impl Reporter for JsonEvents {
+    fn report(&mut self, msg: &Msg) {
+        let file_id = msg.file_id;
+
+        match &msg.msg_type {
+            MsgType::Starting { name, total_size } => {
+                let file = self.names.entry(file_id).or_insert_with(|| name.clone());
+                Event::Starting {
+                    file_id,
+                    file,
+                    total_size: *total_size,
+                }
+                .print();
+            }
+            MsgType::Written { bytes } => Event::Progress {
+                file_id,
+                file: self.name(file_id),
+                bytes: *bytes,
+            }
+            .print(),
+            MsgType::Done => {
+                let file = self.names.remove(&file_id).unwrap_or_default();
+                Event::Done {
+                    file_id,
+                    file: &file,
+                }
+                .print();
+            }
+            MsgType::Error { fileinfo, error } => {
+                self.names.remove(&file_id);
+                Event::Error {
+                    file_id,
+                    file: &fileinfo.file_name(),
+                    reason: error.to_string(),
+                }
+                .print();
+            }
+        }
+    }
+}
+
+impl JsonEvents {
+    fn name(&self, file_id: usize) -> &str {
+        self.names
+            .get(&file_id)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
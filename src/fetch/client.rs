@@ -0,0 +1,87 @@
+use reqwest::{Certificate, Proxy, Url};
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Options used to build the http client shared by all downloads.
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    /// Proxy used for all requests.
+    pub proxy: Option<Url>,
+    /// Pem file with extra root certificates to trust, may contain multiple certificates.
+    pub ca_bundle: Option<PathBuf>,
+    pub connect_timeout: Option<Duration>,
+    /// Timeout for each read from the connection, not for the whole download.
+    pub read_timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientOptions {
+    pub fn build(&self) -> Result<reqwest::Client, ClientError> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy.clone()).map_err(ClientError::InvalidProxy)?;
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path)
+                .map_err(|e| ClientError::CouldNotReadCaBundle(path.clone(), e))?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .map_err(|e| ClientError::InvalidCaBundle(path.clone(), e))?;
+
+            if certificates.is_empty() {
+                return Err(ClientError::NoCertificatesInCaBundle(path.clone()));
+            }
+
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        builder.build().map_err(ClientError::FailedToBuild)
+    }
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    InvalidProxy(reqwest::Error),
+    CouldNotReadCaBundle(PathBuf, std::io::Error),
+    InvalidCaBundle(PathBuf, reqwest::Error),
+    NoCertificatesInCaBundle(PathBuf),
+    FailedToBuild(reqwest::Error),
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::InvalidProxy(e) => write!(f, "Invalid proxy: {e}"),
+            ClientError::CouldNotReadCaBundle(path, e) => {
+                write!(f, "Could not read ca bundle {}: {e}", path.display())
+            }
+            ClientError::InvalidCaBundle(path, e) => {
+                write!(f, "Invalid ca bundle {}: {e}", path.display())
+            }
+            ClientError::NoCertificatesInCaBundle(path) => {
+                write!(f, "No certificates found in ca bundle {}", path.display())
+            }
+            ClientError::FailedToBuild(e) => write!(f, "Could not create http client: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
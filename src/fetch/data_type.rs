@@ -0,0 +1,95 @@
+use super::PeriodName;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use std::fmt::Display;
+
+/// Kind of data in a file, binance publishes each kind in its own directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataType {
+    /// Bars for a timeframe
+    #[default]
+    Klines,
+    /// Every trade
+    Trades,
+    /// Trades filled at the same price and time by the same taker order, grouped together
+    #[value(alias = "aggTrades")]
+    AggTrades,
+    /// Bars of the mark price, futures only
+    #[value(alias = "markPriceKlines")]
+    MarkPriceKlines,
+    /// Bars of the index price, futures only
+    #[value(alias = "indexPriceKlines")]
+    IndexPriceKlines,
+    /// Funding rate of each funding event, futures only and published monthly
+    #[value(alias = "fundingRate")]
+    FundingRate,
+    /// Depth of the order book at percentages from the mid price over the day, futures only
+    #[value(alias = "bookDepth")]
+    BookDepth,
+}
+
+impl DataType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataType::Klines => "klines",
+            DataType::Trades => "trades",
+            DataType::AggTrades => "aggTrades",
+            DataType::MarkPriceKlines => "markPriceKlines",
+            DataType::IndexPriceKlines => "indexPriceKlines",
+            DataType::FundingRate => "fundingRate",
+            DataType::BookDepth => "bookDepth",
+        }
+    }
+
+    /// Klines and mark and index price klines are published per timeframe.
+    pub fn has_timeframe(&self) -> bool {
+        matches!(
+            self,
+            DataType::Klines | DataType::MarkPriceKlines | DataType::IndexPriceKlines
+        )
+    }
+
+    /// Data that binance only publishes for futures.
+    pub fn is_futures_only(&self) -> bool {
+        matches!(
+            self,
+            DataType::MarkPriceKlines
+                | DataType::IndexPriceKlines
+                | DataType::FundingRate
+                | DataType::BookDepth
+        )
+    }
+
+    /// Funding rates are only published monthly, and bookDepth only daily.
+    pub fn is_published(&self, period_name: PeriodName) -> bool {
+        match self {
+            DataType::FundingRate => period_name == PeriodName::Monthly,
+            DataType::BookDepth => period_name == PeriodName::Daily,
+            _ => true,
+        }
+    }
+
+    /// The data type of a file downloaded by cryptory, e.g. BTCUSDT-aggTrades-2025-01-01.zip.
+    /// Kline files have a timeframe instead of a data type in their name,
+    /// mark and index price klines have the same names as klines and are seen as klines.
+    pub fn from_file_name(file_name: &str) -> Option<DataType> {
+        let mut parts = file_name.split('-').skip(1);
+        match parts.next()? {
+            "trades" => Some(DataType::Trades),
+            "aggTrades" => Some(DataType::AggTrades),
+            "fundingRate" => Some(DataType::FundingRate),
+            "bookDepth" => Some(DataType::BookDepth),
+            timeframe if timeframe.parse::<crate::TimeFrame>().is_ok() => Some(DataType::Klines),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -1,36 +1,56 @@
 use super::{
     period::{DateIterator, PeriodName},
-    DateHelper, FormattedDate, Period,
+    DataType, DateHelper, FormattedDate, Market, Period,
 };
 
-use crate::{ticker::Tickerator, Ticker, TimeFrame};
+use crate::{Ticker, Tickerator, TimeFrame};
 
 use reqwest::Url;
+use serde::{Deserialize, Serialize};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// The fileInfoIterator is used to iterate over the files
 /// and urls that should be downloaded from binance.
+/// Files are ordered by ticker, then timeframe, then date.
 #[derive(Debug)]
 pub struct FileInfoIterator {
     period: Period,
+    market: Market,
+    data_type: DataType,
     ticker_iter: Tickerator,
     curr_ticker: Option<Ticker>,
     date_iter: DateIterator,
-    timeframe: TimeFrame,
+    timeframes: Vec<TimeFrame>,
+    curr_timeframe: usize,
     curr_id: usize,
+    hosts: Vec<Url>,
+    output_dir: PathBuf,
 }
 
 impl Iterator for FileInfoIterator {
     type Item = FileInfo;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let ticker = self.curr_ticker.as_mut()?;
+        // only klines are split by timeframe
+        let timeframe_count = match self.data_type.has_timeframe() {
+            true => self.timeframes.len(),
+            false => 1,
+        };
+        if timeframe_count == 0 {
+            return None;
+        }
+        self.curr_ticker.as_ref()?;
 
         let curr_date = if let Some(curr_date) = self.date_iter.next() {
             curr_date
         } else {
-            *ticker = self.ticker_iter.next()?;
+            self.curr_timeframe += 1;
+            if self.curr_timeframe == timeframe_count {
+                self.curr_timeframe = 0;
+                self.curr_ticker = self.ticker_iter.next();
+                self.curr_ticker.as_ref()?;
+            }
             self.date_iter.reset();
             self.date_iter.next().expect("we just reset the date_iter.")
         };
@@ -41,61 +61,166 @@ impl Iterator for FileInfoIterator {
         let file_id = self.curr_id;
         self.curr_id += 1;
 
+        let dataset = Dataset {
+            market: self.market,
+            data_type: self.data_type,
+            timeframe: self
+                .data_type
+                .has_timeframe()
+                .then(|| self.timeframes[self.curr_timeframe]),
+        };
+
         Some(FileInfo::new(
-            ticker,
-            &self.timeframe,
+            self.curr_ticker.as_ref().expect("checked above"),
+            dataset,
             period_name,
             formatted_date,
             file_id,
+            &self.hosts,
+            &self.output_dir,
         ))
     }
 }
 
+/// The items in the order they were first given.
+fn unique<T: PartialEq>(items: Vec<T>) -> Vec<T> {
+    let mut unique = Vec::with_capacity(items.len());
+    for item in items {
+        if !unique.contains(&item) {
+            unique.push(item);
+        }
+    }
+    unique
+}
+
 impl FileInfoIterator {
-    pub fn new(tickers: Vec<Ticker>, timeframe: TimeFrame, period: Period) -> FileInfoIterator {
+    /// Duplicate tickers and timeframes are only iterated once,
+    /// two files with the same path would be written at the same time.
+    pub fn new(
+        tickers: Vec<Ticker>,
+        timeframes: Vec<TimeFrame>,
+        period: Period,
+    ) -> FileInfoIterator {
+        let tickers = unique(tickers);
+        let timeframes = unique(timeframes);
         let date_iter = DateIterator::from(period.clone());
         let mut ticker_iter = Tickerator::from(tickers);
         let curr_ticker = ticker_iter.next();
 
         Self {
             period,
-            timeframe,
+            market: Market::Spot,
+            data_type: DataType::Klines,
+            timeframes,
+            curr_timeframe: 0,
             ticker_iter,
             curr_ticker,
             date_iter,
             curr_id: 1,
+            hosts: vec![default_base_url()],
+            // relative paths are resolved when the files are opened, so the
+            // current directory does not have to exist until then.
+            output_dir: PathBuf::new(),
+        }
+    }
+
+    /// Set the market to download from, defaults to spot.
+    pub fn with_market(mut self, market: Market) -> Self {
+        self.market = market;
+        self
+    }
+
+    /// Set the kind of data to download, defaults to klines.
+    /// Timeframes are ignored for other kinds of data.
+    pub fn with_data_type(mut self, data_type: DataType) -> Self {
+        self.data_type = data_type;
+        self
+    }
+
+    /// Set the directory the files are written to, by default the paths of the files
+    /// are relative to the current directory.
+    pub fn with_output_dir(mut self, output_dir: PathBuf) -> Self {
+        self.output_dir = output_dir;
+        self
+    }
+
+    /// Set the hosts to download from, the first one is the main host
+    /// and the rest are fallbacks tried in order.
+    pub fn with_hosts(mut self, hosts: Vec<Url>) -> Self {
+        if !hosts.is_empty() {
+            self.hosts = hosts;
         }
+        self
     }
 }
 
-#[derive(Debug, Clone)]
+/// What a file holds, this decides its url and name.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Dataset {
+    pub market: Market,
+    pub data_type: DataType,
+    /// Only klines have a timeframe.
+    pub timeframe: Option<TimeFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
     pub source_url: Url,
+    /// Urls to try in order if the file could not be fetched from source_url.
+    pub mirror_urls: Vec<Url>,
     pub file_path: PathBuf,
     pub file_id: usize,
 }
 
-
 impl FileInfo {
-    pub fn new(
+    pub(crate) fn new(
         ticker: &Ticker,
-        timeframe: &TimeFrame,
+        dataset: Dataset,
         period_name: PeriodName,
         formatted_date: FormattedDate,
         file_id: usize,
+        hosts: &[Url],
+        output_dir: &Path,
     ) -> Self {
-        let file_name = format!("{ticker}-{timeframe}-{formatted_date}.zip");
-        let url_str = format!("https://data.binance.vision/data/spot/{period_name}/klines/{ticker}/{timeframe}/{file_name}");
+        let market = dataset.market.dir();
+        let data_type = dataset.data_type;
+        let (file_name, url_path) = match dataset.timeframe {
+            Some(timeframe) => {
+                let file_name = format!("{ticker}-{timeframe}-{formatted_date}.zip");
+                let url_path = format!(
+                    "data/{market}/{period_name}/{data_type}/{ticker}/{timeframe}/{file_name}"
+                );
+                (file_name, url_path)
+            }
+            None => {
+                let file_name = format!("{ticker}-{data_type}-{formatted_date}.zip");
+                let url_path =
+                    format!("data/{market}/{period_name}/{data_type}/{ticker}/{file_name}");
+                (file_name, url_path)
+            }
+        };
 
-        let source_url = Url::parse(&url_str).expect("expect correct url format above");
+        let mut urls = hosts.iter().map(|host| {
+            host.join(&url_path)
+                .expect("expect correct url format above")
+        });
 
-        // TODO: Replace this with a field on the fileinfoiterator
-        // and pass to the new function
-        let mut file_path = std::env::current_dir().expect("expect to be able to open current dir");
-        file_path.push(file_name);
+        let source_url = urls.next().expect("expect atleast one host");
+        let mirror_urls = urls.collect();
+
+        // futures klines, mark and index price klines all have the same names,
+        // so futures files are kept apart in the same directories as at binance.
+        let file_path = match dataset.market {
+            Market::Spot => output_dir.join(file_name),
+            _ => output_dir
+                .join(market)
+                .join(data_type.as_str())
+                .join(file_name),
+        };
 
         FileInfo {
             source_url,
+            mirror_urls,
             file_path,
             file_id,
         }
@@ -108,4 +233,176 @@ impl FileInfo {
             .expect("valid str")
             .to_string()
     }
+
+    /// All urls the file can be fetched from, in the order they should be tried.
+    pub fn urls(&self) -> impl Iterator<Item = &Url> {
+        std::iter::once(&self.source_url).chain(&self.mirror_urls)
+    }
+}
+
+pub const DEFAULT_BASE_URL: &str = "https://data.binance.vision/";
+
+fn default_base_url() -> Url {
+    Url::parse(DEFAULT_BASE_URL).expect("valid default url")
+}
+
+/// Parse the base url of a host serving the binance public data.
+/// A trailing slash is added so the host can be served from a sub path.
+pub fn parse_base_url(input: &str) -> Result<Url, String> {
+    let mut url = Url::parse(input).map_err(|e| format!("Invalid url {input}: {e}"))?;
+
+    if url.cannot_be_a_base() {
+        return Err(format!("{input} can not be used as a base url"));
+    }
+
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PeriodName;
+
+    use chrono::NaiveDate;
+
+    #[test]
+    fn iterate_tickers_timeframes_and_dates() {
+        let period = Period::new(
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 2),
+            PeriodName::Daily,
+        );
+        let tickers = vec!["btcusdt".parse().unwrap(), "ethusdt".parse().unwrap()];
+        let timeframes = vec!["1h".parse().unwrap(), "1d".parse().unwrap()];
+
+        let files: Vec<(usize, String)> = FileInfoIterator::new(tickers, timeframes, period)
+            .with_output_dir(PathBuf::new())
+            .map(|file| (file.file_id, file.file_name()))
+            .collect();
+
+        let expected = [
+            "BTCUSDT-1h-2025-01-01",
+            "BTCUSDT-1h-2025-01-02",
+            "BTCUSDT-1d-2025-01-01",
+            "BTCUSDT-1d-2025-01-02",
+            "ETHUSDT-1h-2025-01-01",
+            "ETHUSDT-1h-2025-01-02",
+            "ETHUSDT-1d-2025-01-01",
+            "ETHUSDT-1d-2025-01-02",
+        ];
+        let expected: Vec<(usize, String)> = expected
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (i + 1, name.to_string()))
+            .collect();
+        assert_eq!(expected, files);
+
+        // duplicates are only iterated once, in the order they were first given
+        let period = Period::new(
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            None,
+            PeriodName::Daily,
+        );
+        let tickers = ["btcusdt", "ethusdt", "BTCUSDT"]
+            .iter()
+            .map(|ticker| ticker.parse().unwrap())
+            .collect();
+        let timeframes = ["1h", "1d", "1h"]
+            .iter()
+            .map(|timeframe| timeframe.parse().unwrap())
+            .collect();
+        let files: Vec<String> = FileInfoIterator::new(tickers, timeframes, period)
+            .map(|file| file.file_name())
+            .collect();
+        assert_eq!(
+            vec![
+                "BTCUSDT-1h-2025-01-01",
+                "BTCUSDT-1d-2025-01-01",
+                "ETHUSDT-1h-2025-01-01",
+                "ETHUSDT-1d-2025-01-01",
+            ],
+            files
+        );
+    }
+
+    #[test]
+    fn futures_urls_and_paths() {
+        let period = Period::new(
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            None,
+            PeriodName::Monthly,
+        );
+        let tickers = vec!["btcusdt".parse().unwrap()];
+        let hosts = vec![parse_base_url("https://data.binance.vision/").unwrap()];
+
+        let file = FileInfoIterator::new(tickers, vec!["1h".parse().unwrap()], period)
+            .with_market(Market::FuturesUm)
+            .with_data_type(DataType::MarkPriceKlines)
+            .with_hosts(hosts)
+            .next()
+            .unwrap();
+
+        assert_eq!(
+            "https://data.binance.vision/data/futures/um/monthly/markPriceKlines/BTCUSDT/1h/BTCUSDT-1h-2025-01.zip",
+            file.source_url.as_str()
+        );
+        assert_eq!(
+            PathBuf::from("futures/um/markPriceKlines/BTCUSDT-1h-2025-01.zip"),
+            file.file_path
+        );
+    }
+
+    #[test]
+    fn base_url_with_sub_path() {
+        let with_slash = parse_base_url("https://mirror.internal/binance/").unwrap();
+        let without_slash = parse_base_url("https://mirror.internal/binance").unwrap();
+        assert_eq!("https://mirror.internal/binance/", with_slash.as_str());
+        assert_eq!(with_slash, without_slash);
+        assert_eq!(
+            "https://data.binance.vision/",
+            parse_base_url("https://data.binance.vision")
+                .unwrap()
+                .as_str()
+        );
+
+        assert!(parse_base_url("data.binance.vision").is_err());
+        assert!(parse_base_url("mailto:data@binance.vision").is_err());
+    }
+
+    #[test]
+    fn mirrors_tried_in_order() {
+        let period = Period::new(
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            None,
+            PeriodName::Daily,
+        );
+        let hosts = vec![
+            parse_base_url("https://data.binance.vision").unwrap(),
+            parse_base_url("https://mirror.internal/binance").unwrap(),
+            parse_base_url("http://localhost:8080/").unwrap(),
+        ];
+
+        let file = FileInfoIterator::new(
+            vec!["btcusdt".parse().unwrap()],
+            vec!["1d".parse().unwrap()],
+            period,
+        )
+        .with_hosts(hosts)
+        .with_output_dir(PathBuf::new())
+        .next()
+        .unwrap();
+
+        let urls: Vec<&str> = file.urls().map(|url| url.as_str()).collect();
+        assert_eq!(
+            vec![
+                "https://data.binance.vision/data/spot/daily/klines/BTCUSDT/1d/BTCUSDT-1d-2025-01-01.zip",
+                "https://mirror.internal/binance/data/spot/daily/klines/BTCUSDT/1d/BTCUSDT-1d-2025-01-01.zip",
+                "http://localhost:8080/data/spot/daily/klines/BTCUSDT/1d/BTCUSDT-1d-2025-01-01.zip",
+            ],
+            urls
+        );
+    }
 }
@@ -0,0 +1,41 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use std::fmt::Display;
+
+/// Market the data comes from, each has its own directory at binance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Market {
+    #[default]
+    Spot,
+    /// USD-M futures, margined in USDT or USDC
+    FuturesUm,
+    /// COIN-M futures, margined in the base asset
+    FuturesCm,
+}
+
+impl Market {
+    /// Directory of the market, at binance and in the output directory.
+    pub fn dir(&self) -> &'static str {
+        match self {
+            Market::Spot => "spot",
+            Market::FuturesUm => "futures/um",
+            Market::FuturesCm => "futures/cm",
+        }
+    }
+
+    pub fn is_futures(&self) -> bool {
+        !matches!(self, Market::Spot)
+    }
+}
+
+impl Display for Market {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Market::Spot => write!(f, "spot"),
+            Market::FuturesUm => write!(f, "futures-um"),
+            Market::FuturesCm => write!(f, "futures-cm"),
+        }
+    }
+}
@@ -1,30 +1,118 @@
+mod client;
+mod data_type;
 mod fileinfo;
+mod market;
 mod period;
+mod throttle;
 mod timeframe;
 
 use tokio::io::AsyncWriteExt;
 use futures_util::StreamExt;
 
-pub use fileinfo::{FileInfo, FileInfoIterator};
-pub use period::Period;
+pub use client::{ClientError, ClientOptions};
+pub use data_type::DataType;
+pub use fileinfo::{parse_base_url, FileInfo, FileInfoIterator, DEFAULT_BASE_URL};
+pub use market::Market;
+pub use period::{parse_end_date, parse_start_date, DateExpr, Period, PeriodError, PeriodName};
+pub use throttle::{parse_rate, Throttle};
 pub use timeframe::TimeFrame;
 
 use chrono::NaiveDate;
+use reqwest::StatusCode;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 use std::fmt::Display;
+use std::io::ErrorKind;
 
 #[derive(Debug)]
 pub enum Error {
-    FailedToSendRequest,
+    /// Could not connect to the host or send the request.
+    FailedToSendRequest(reqwest::Error),
+    /// The host responded with 404.
     CouldNotFindFileAtHost,
-    CouldNotOpenFile(std::io::ErrorKind),
-    FailedToWriteToFile,
+    /// The host responded with another status that is not a success.
+    BadStatus(StatusCode),
+    CouldNotOpenFile(std::io::Error),
+    /// The partially written file has been removed.
+    FailedToWriteToFile(std::io::Error),
+    /// The connection failed while the file was being downloaded,
+    /// the partially written file has been removed.
+    FailedToReadStream(reqwest::Error),
+    /// The host sent fewer or more bytes than it said it would,
+    /// the file has been removed.
+    Truncated {
+        expected: u64,
+        received: u64,
+    },
+    /// The downloaded file is not a valid zip archive, it has been removed.
+    InvalidArchive(zip::result::ZipError),
+    /// The download was cancelled, any partially written file has been removed.
+    Cancelled,
+}
+
+impl Error {
+    /// The file was not downloaded since it already exists.
+    pub fn already_exists(&self) -> bool {
+        matches!(self, Error::CouldNotOpenFile(e) if e.kind() == ErrorKind::AlreadyExists)
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Error::FailedToSendRequest(e) => write!(f, "Failed to send request: {}", Causes(e)),
+            Error::CouldNotFindFileAtHost => write!(f, "Could not find file at host (404)"),
+            Error::BadStatus(status) => write!(f, "Host responded with {status}"),
+            Error::CouldNotOpenFile(e) => write!(f, "Could not open file: {e}"),
+            Error::FailedToWriteToFile(e) => write!(f, "Failed to write to file: {e}"),
+            Error::FailedToReadStream(e) => {
+                write!(f, "Connection failed during download: {}", Causes(e))
+            }
+            Error::Truncated { expected, received } => write!(
+                f,
+                "Download was cut short, received {received} of {expected} bytes"
+            ),
+            Error::InvalidArchive(e) => write!(f, "Downloaded file is not a valid zip: {e}"),
+            Error::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::FailedToSendRequest(e) | Error::FailedToReadStream(e) => Some(e),
+            Error::CouldNotOpenFile(e) | Error::FailedToWriteToFile(e) => Some(e),
+            Error::InvalidArchive(e) => Some(e),
+            Error::CouldNotFindFileAtHost
+            | Error::BadStatus(_)
+            | Error::Truncated { .. }
+            | Error::Cancelled => None,
+        }
+    }
+}
+
+/// Displays an error followed by its underlying causes,
+/// reqwest only tells what failed at the top level, e.g. "error sending request for url".
+struct Causes<'a>(&'a dyn std::error::Error);
+
+impl Display for Causes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut last = self.0.to_string();
+        write!(f, "{last}")?;
+
+        let mut source = self.0.source();
+        while let Some(cause) = source {
+            // some errors already include the message of their cause
+            let cause_msg = cause.to_string();
+            if !last.contains(&cause_msg) {
+                write!(f, ": {cause_msg}")?;
+            }
+            last = cause_msg;
+            source = cause.source();
+        }
+        Ok(())
     }
 }
 
@@ -33,9 +121,18 @@ async fn open_file(
     path: std::path::PathBuf,
     overwrite: bool,
 ) -> Result<tokio::fs::File, std::io::Error> {
+    // futures files are downloaded into subdirectories.
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
     let mut open_options = tokio::fs::OpenOptions::new();
 
-    if !overwrite {
+    if overwrite {
+        // an existing file is longer than a smaller download,
+        // its old end would still look like a valid archive.
+        open_options.truncate(true);
+    } else {
         // create is ignored when create new is set.
         // so we can always include it.
         open_options.create_new(true);
@@ -43,39 +140,68 @@ async fn open_file(
     open_options.create(true).write(true).open(path).await
 }
 
+/// Don't leave a partial file behind, it would look like a finished download
+/// and make a retry fail since the file already exists.
+async fn remove_partial_file(file: tokio::fs::File, path: &std::path::Path) {
+    drop(file);
+    let _ = tokio::fs::remove_file(path).await;
+}
+
+/// Request the file from each of its urls in order until one responds successfully.
+/// If all of them fail, the error of the last url is returned.
+async fn send_request(
+    fileinfo: &FileInfo,
+    client: &reqwest::Client,
+) -> Result<reqwest::Response, Error> {
+    let mut error = None;
+
+    for url in fileinfo.urls() {
+        match client.get(url.clone()).send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => {
+                error = Some(Error::CouldNotFindFileAtHost)
+            }
+            Ok(response) => error = Some(Error::BadStatus(response.status())),
+            Err(e) => error = Some(Error::FailedToSendRequest(e)),
+        }
+    }
+
+    Err(error.expect("expect atleast one url"))
+}
+
 pub async fn download_file(
     fileinfo: FileInfo,
     local_client: reqwest::Client,
     local_tx: mpsc::UnboundedSender<Msg>,
     overwrite: bool,
+    throttle: Option<Throttle>,
+    cancel: CancellationToken,
 ) {
     let file_name = fileinfo.file_name();
     let send_msg = move |msg: MsgType| {
         let _ = local_tx.send(Msg::new(fileinfo.file_id, msg));
     };
 
-    let Ok(request) = local_client.get(fileinfo.source_url.clone()).send().await else {
-        send_msg(MsgType::Error {
-            fileinfo: fileinfo.clone(),
-            error: Error::FailedToSendRequest,
-        });
-        return;
+    let request = tokio::select! {
+        biased;
+        _ = cancel.cancelled() => Err(Error::Cancelled),
+        request = send_request(&fileinfo, &local_client) => request,
     };
 
-    if !request.status().is_success() {
-        send_msg(MsgType::Error {
-            fileinfo,
-            error: Error::CouldNotFindFileAtHost,
-        });
-        return;
-    }
+    let request = match request {
+        Ok(request) => request,
+        Err(error) => {
+            send_msg(MsgType::Error { fileinfo, error });
+            return;
+        }
+    };
 
     let mut file = match open_file(fileinfo.file_path.clone(), overwrite).await {
         Ok(file) => file,
         Err(e) => {
             send_msg(MsgType::Error {
                 fileinfo,
-                error: Error::CouldNotOpenFile(e.kind()),
+                error: Error::CouldNotOpenFile(e),
             });
             return;
         }
@@ -88,22 +214,87 @@ pub async fn download_file(
     });
 
     let mut stream = request.bytes_stream();
+    let mut written = 0;
+
+    loop {
+        let item = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => Err(Error::Cancelled),
+            item = stream.next() => match item {
+                Some(Ok(item)) => Ok(Some(item)),
+                Some(Err(e)) => Err(Error::FailedToReadStream(e)),
+                None => Ok(None),
+            },
+        };
+
+        let item = match item {
+            Ok(Some(item)) => item,
+            Ok(None) => break,
+            Err(error) => {
+                remove_partial_file(file, &fileinfo.file_path).await;
+                send_msg(MsgType::Error { fileinfo, error });
+                return;
+            }
+        };
 
-    while let Some(Ok(item)) = stream.next().await {
-        let Ok(bytes) = file.write(&item).await else {
+        if let Some(throttle) = &throttle {
+            throttle.consume(item.len() as u64).await;
+        }
+
+        if let Err(e) = file.write_all(&item).await {
+            remove_partial_file(file, &fileinfo.file_path).await;
             send_msg(MsgType::Error {
                 fileinfo,
-                error: Error::FailedToWriteToFile,
+                error: Error::FailedToWriteToFile(e),
             });
             return;
-        };
+        }
+        written += item.len() as u64;
 
         send_msg(MsgType::Written {
-            bytes: bytes as u64,
+            bytes: item.len() as u64,
         })
     }
 
-    send_msg(MsgType::Done);
+    let result = match check_length(total_size, written) {
+        Ok(()) => finish_file(&mut file, &fileinfo.file_path).await,
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(()) => send_msg(MsgType::Done),
+        Err(error) => {
+            remove_partial_file(file, &fileinfo.file_path).await;
+            send_msg(MsgType::Error { fileinfo, error });
+        }
+    }
+}
+
+/// The host sent as many bytes as it said it would, when it said so.
+fn check_length(total_size: Option<u64>, written: u64) -> Result<(), Error> {
+    match total_size {
+        Some(expected) if expected != written => Err(Error::Truncated {
+            expected,
+            received: written,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Flush the file and check that it is a valid zip archive.
+/// A download that was cut short is missing the central directory at the end of the archive.
+async fn finish_file(file: &mut tokio::fs::File, path: &std::path::Path) -> Result<(), Error> {
+    file.flush().await.map_err(Error::FailedToWriteToFile)?;
+
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&path).map_err(Error::CouldNotOpenFile)?;
+        zip::ZipArchive::new(file)
+            .map(|_| ())
+            .map_err(Error::InvalidArchive)
+    })
+    .await
+    .expect("expect validating the archive not to panic")
 }
 
 pub struct Msg {
@@ -161,3 +352,80 @@ impl Display for FormattedDate {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    #[test]
+    fn fail_when_cut_short() {
+        assert!(check_length(None, 10).is_ok());
+        assert!(check_length(Some(10), 10).is_ok());
+        assert!(matches!(
+            check_length(Some(10), 4),
+            Err(Error::Truncated {
+                expected: 10,
+                received: 4
+            })
+        ));
+    }
+
+    fn archive(rows: usize) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut archive));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("klines.csv", options).unwrap();
+        for _ in 0..rows {
+            zip.write_all(b"1735689600000,1,1,1,1,1\n").unwrap();
+        }
+        zip.finish().unwrap();
+        archive
+    }
+
+    #[tokio::test]
+    async fn fail_on_invalid_archive() {
+        let archive = archive(1);
+
+        let path = std::env::temp_dir().join(format!("cryptory-finish-{}.zip", std::process::id()));
+        let mut results = Vec::new();
+        for content in [&archive[..], &archive[..archive.len() - 10]] {
+            let mut file = tokio::fs::File::create(&path).await.unwrap();
+            file.write_all(content).await.unwrap();
+            results.push(finish_file(&mut file, &path).await);
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::InvalidArchive(_))));
+    }
+
+    #[tokio::test]
+    async fn overwrite_larger_file() {
+        let existing = archive(100);
+        let download = archive(1);
+
+        let path =
+            std::env::temp_dir().join(format!("cryptory-overwrite-{}.zip", std::process::id()));
+        std::fs::write(&path, &existing).unwrap();
+
+        // a cut short download must not pass because of the end of the old file
+        let mut file = open_file(path.clone(), true).await.unwrap();
+        file.write_all(&download[..download.len() - 10])
+            .await
+            .unwrap();
+        let cut_short = finish_file(&mut file, &path).await;
+
+        let mut file = open_file(path.clone(), true).await.unwrap();
+        file.write_all(&download).await.unwrap();
+        let complete = finish_file(&mut file, &path).await;
+        let size = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(cut_short, Err(Error::InvalidArchive(_))));
+        assert!(complete.is_ok());
+        assert_eq!(download.len() as u64, size);
+    }
+}
@@ -1,9 +1,11 @@
 use crate::fetch::DateHelper;
 
-use chrono::{Datelike, NaiveDate};
-use clap::Subcommand;
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use clap::{ArgMatches, FromArgMatches, Subcommand};
 
 use std::fmt::Display;
+use std::ops::Range;
 use std::str::FromStr;
 
 #[derive(Debug)]
@@ -53,49 +55,266 @@ impl Iterator for DateIterator {
 /// Format:
 /// 2025-01-01 for Daily
 /// 2025-01 or 2025-01-01 for Monthly (date will be ignored)
-#[derive(Debug, Clone, Subcommand)]
+///
+/// Dates can also be given relative to today in UTC, see [`DateExpr`].
+#[derive(Debug, Clone)]
 pub enum Period {
     /// Fetch file(s) for each day in the period from start to end date.
     Daily {
-        /// Select the first date you want data from.
         start_date: NaiveDate,
+        end_date: Option<NaiveDate>,
+    },
+
+    /// Fetch file(s) for each month in the period from start to end date.
+    Monthly {
+        start_date: NaiveDate,
+        end_date: Option<NaiveDate>,
+    },
+}
+
+/// The arguments of a [`Period`], start dates are kept as ranges
+/// so that e.g. `daily 2024` covers the whole year when no end date is given.
+#[derive(Debug, Clone, Subcommand)]
+enum PeriodArgs {
+    /// Fetch file(s) for each day in the period from start to end date.
+    Daily {
+        /// Select the first date you want data from.
+        /// Also accepts today, yesterday, -30d, -6mo, ytd, 2024, 2024-Q3 or 2024-03
+        #[arg(value_parser = parse_start_range, allow_hyphen_values = true)]
+        start_date: DateRange,
         /// Select the last date you want data to.
-        /// If left out, will only download the day of start_date
-        #[arg(short)]
+        /// If left out, will only download the day of start_date,
+        /// or every day of it when it is a range like 2024
+        #[arg(short, value_parser = parse_end_date, allow_hyphen_values = true)]
         end_date: Option<NaiveDate>,
     },
 
     /// Fetch file(s) for each month in the period from start to end date.
     Monthly {
         /// Select the first date you want data from.
-        #[arg(value_parser = parse_monthly)]
-        start_date: NaiveDate,
+        /// Also accepts today, yesterday, -30d, -6mo, ytd, 2024 or 2024-Q3
+        #[arg(value_parser = parse_monthly_range, allow_hyphen_values = true)]
+        start_date: DateRange,
         /// Select the last date you want data to.
-        /// If left out, will only download month of start_date
-        #[arg(short, value_parser = parse_monthly)]
+        /// If left out, will only download month of start_date,
+        /// or every month of it when it is a range like 2024
+        #[arg(short, value_parser = parse_monthly_end, allow_hyphen_values = true)]
         end_date: Option<NaiveDate>,
     },
 }
 
-//TODO: Create better parsing for period so start_date >= end_date etc
-pub fn parse_monthly(input: &str) -> Result<NaiveDate, &'static str> {
+/// First and last day of a date expression given as start date.
+#[derive(Debug, Clone, Copy)]
+struct DateRange {
+    first: NaiveDate,
+    last: NaiveDate,
+}
+
+fn parse_start_range(input: &str) -> Result<DateRange, String> {
+    Ok(DateRange {
+        first: parse_start_date(input)?,
+        last: parse_end_date(input)?,
+    })
+}
+
+fn parse_monthly_range(input: &str) -> Result<DateRange, String> {
+    Ok(DateRange {
+        first: parse_monthly(input)?,
+        last: parse_monthly_end(input)?,
+    })
+}
+
+impl From<PeriodArgs> for Period {
+    fn from(value: PeriodArgs) -> Self {
+        match value {
+            PeriodArgs::Daily {
+                start_date,
+                end_date,
+            } => Period::Daily {
+                start_date: start_date.first,
+                end_date: Some(end_date.unwrap_or(start_date.last)),
+            },
+            PeriodArgs::Monthly {
+                start_date,
+                end_date,
+            } => Period::Monthly {
+                start_date: start_date.first,
+                end_date: Some(end_date.unwrap_or(start_date.last)),
+            },
+        }
+    }
+}
+
+impl FromArgMatches for Period {
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        PeriodArgs::from_arg_matches(matches).map(Period::from)
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        *self = Period::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
+
+impl Subcommand for Period {
+    fn augment_subcommands(cmd: clap::Command) -> clap::Command {
+        PeriodArgs::augment_subcommands(cmd)
+    }
+
+    fn augment_subcommands_for_update(cmd: clap::Command) -> clap::Command {
+        PeriodArgs::augment_subcommands_for_update(cmd)
+    }
+
+    fn has_subcommand(name: &str) -> bool {
+        PeriodArgs::has_subcommand(name)
+    }
+}
+
+pub fn parse_monthly(input: &str) -> Result<NaiveDate, String> {
     let date = match NaiveDate::from_str(input) {
 
         // NOTE: this is to fix bug where we iterate months
         // when we check if current_date < end_date, this needs to 
         // be set the first in the month.
         Ok(date) => date.with_day(1).expect("valid day"),
-        Err(_) => {
-            let mut s: String = input.into();
-            s.push_str("-01");
-            NaiveDate::from_str(&s).map_err(|_| "Invalid date format")?
-        }
+        Err(_) => parse_start_date(input)?.with_day(1).expect("valid day"),
     };
     Ok(date)
 }
 
+/// Like [`parse_monthly`], but ranges like 2024 resolve to their last month.
+pub fn parse_monthly_end(input: &str) -> Result<NaiveDate, String> {
+    Ok(parse_end_date(input)?.with_day(1).expect("valid day"))
+}
+
+/// Parse a date or date expression, ranges resolve to their first day.
+pub fn parse_start_date(input: &str) -> Result<NaiveDate, String> {
+    DateExpr::from_str(input)?
+        .first(today())
+        .ok_or_else(|| invalid_date(input))
+}
+
+/// Parse a date or date expression, ranges resolve to their last day.
+pub fn parse_end_date(input: &str) -> Result<NaiveDate, String> {
+    DateExpr::from_str(input)?
+        .last(today())
+        .ok_or_else(|| invalid_date(input))
+}
+
+fn today() -> NaiveDate {
+    chrono::Utc::now().date_naive()
+}
+
+/// A date, or a range of dates, relative to today in UTC.
+///
+/// Examples: 2025-01-01, today, yesterday, -30d, -6mo, ytd, 2024, 2024-Q3, 2024-03
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateExpr {
+    Date(NaiveDate),
+    Today,
+    Yesterday,
+    DaysAgo(u32),
+    MonthsAgo(u32),
+    /// From the first of january until today.
+    YearToDate,
+    Year(i32),
+    /// Year and quarter 1-4.
+    Quarter(i32, u32),
+    /// Year and month 1-12.
+    Month(i32, u32),
+}
+
+impl DateExpr {
+    /// First day of the expression, the date itself when it is not a range.
+    /// None when the day is out of the range of dates, e.g. -100000000d.
+    pub fn first(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            DateExpr::Date(date) => Some(date),
+            DateExpr::Today => Some(today),
+            DateExpr::Yesterday => today.pred_opt(),
+            DateExpr::DaysAgo(days) => today.checked_sub_days(chrono::Days::new(days.into())),
+            DateExpr::MonthsAgo(months) => today.checked_sub_months(chrono::Months::new(months)),
+            DateExpr::YearToDate => first_of_month(today.year(), 1),
+            DateExpr::Year(year) => first_of_month(year, 1),
+            DateExpr::Quarter(year, quarter) => first_of_month(year, quarter * 3 - 2),
+            DateExpr::Month(year, month) => first_of_month(year, month),
+        }
+    }
+
+    /// Last day of the expression, the date itself when it is not a range.
+    /// None when the day is out of the range of dates.
+    pub fn last(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            DateExpr::YearToDate => Some(today),
+            DateExpr::Year(year) => last_of_month(year, 12),
+            DateExpr::Quarter(year, quarter) => last_of_month(year, quarter * 3),
+            DateExpr::Month(year, month) => last_of_month(year, month),
+            _ => self.first(today),
+        }
+    }
+}
+
+fn first_of_month(year: i32, month: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, 1)
+}
+
+fn last_of_month(year: i32, month: u32) -> Option<NaiveDate> {
+    first_of_month(year, month)?
+        .checked_add_months(chrono::Months::new(1))?
+        .pred_opt()
+}
+
+fn invalid_date(input: &str) -> String {
+    format!(
+        "Invalid date {input}, expected e.g. 2025-01-01, today, yesterday, -30d, -6mo, ytd, 2024, 2024-Q3 or 2024-03"
+    )
+}
+
+impl FromStr for DateExpr {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || invalid_date(input);
+        let lower = input.trim().to_ascii_lowercase();
+
+        let expr = match lower.as_str() {
+            "today" => DateExpr::Today,
+            "yesterday" => DateExpr::Yesterday,
+            "ytd" => DateExpr::YearToDate,
+            s if s.starts_with('-') && s.ends_with("mo") => {
+                DateExpr::MonthsAgo(s[1..s.len() - 2].parse().map_err(|_| invalid())?)
+            }
+            s if s.starts_with('-') && s.ends_with('d') => {
+                DateExpr::DaysAgo(s[1..s.len() - 1].parse().map_err(|_| invalid())?)
+            }
+            s => {
+                if let Ok(date) = NaiveDate::from_str(s) {
+                    return Ok(DateExpr::Date(date));
+                }
+
+                let mut parts = s.splitn(2, '-');
+                let year: i32 = match parts.next() {
+                    Some(year) if year.len() == 4 => year.parse().map_err(|_| invalid())?,
+                    _ => return Err(invalid()),
+                };
+                match parts.next() {
+                    None => DateExpr::Year(year),
+                    Some(quarter) if quarter.starts_with('q') => match quarter[1..].parse() {
+                        Ok(quarter @ 1..=4) => DateExpr::Quarter(year, quarter),
+                        _ => return Err(invalid()),
+                    },
+                    Some(month) => match month.parse() {
+                        Ok(month @ 1..=12) => DateExpr::Month(year, month),
+                        _ => return Err(invalid()),
+                    },
+                }
+            }
+        };
+        Ok(expr)
+    }
+}
+
 impl Period {
-    #[allow(unused)]
     pub fn new(start_date: NaiveDate, end_date: Option<NaiveDate>, period: PeriodName) -> Self {
         match period {
             PeriodName::Daily => {
@@ -140,19 +359,157 @@ impl Period {
             Period::Monthly { end_date, .. } => *end_date,
         }
     }
+
+    /// Check that the period only covers files that are published.
+    ///
+    /// `today` is the current date in UTC, Binance publishes the file of a day on the day after,
+    /// and the file of a month once the month is over.
+    /// An end date past the latest published file is clamped to it,
+    /// a start date past it or after the end date is an error.
+    pub fn validate(self, today: NaiveDate) -> Result<Period, PeriodError> {
+        self.check_order()?;
+        let start_date = self.start_date();
+        let end_date = self.end_date().unwrap_or(start_date);
+        let latest = self.period_name().latest_published(today);
+
+        if start_date > latest {
+            return Err(PeriodError::NotPublished {
+                period_name: self.period_name(),
+                start_date,
+                latest,
+            });
+        }
+
+        Ok(Period::new(
+            start_date,
+            Some(end_date.min(latest)),
+            self.period_name(),
+        ))
+    }
+
+    /// Check that the end date is not before the start date,
+    /// files that were already downloaded are read without [`Period::validate`].
+    pub fn check_order(&self) -> Result<(), PeriodError> {
+        let start_date = self.start_date();
+        let end_date = self.end_date().unwrap_or(start_date);
+        if end_date < start_date {
+            return Err(PeriodError::EndBeforeStart {
+                start_date,
+                end_date,
+            });
+        }
+        Ok(())
+    }
+
+    /// The times the period covers when its days are in `timezone`,
+    /// from the start of its first day up to the start of the day after its last day.
+    /// Monthly periods cover whole months.
+    pub fn time_range(&self, timezone: Tz) -> Range<DateTime<Utc>> {
+        let start_date = self.start_date();
+        let end_date = self.end_date().unwrap_or(start_date);
+        let after_end = match self.period_name() {
+            PeriodName::Daily => end_date.succ_opt(),
+            PeriodName::Monthly => end_date.checked_add_months(Months::new(1)),
+        }
+        .expect("date out of bounds");
+
+        start_of_day(start_date, timezone)..start_of_day(after_end, timezone)
+    }
+
+    /// The period of the files that hold the times of [`Period::time_range`],
+    /// binance splits files on days in UTC.
+    pub fn in_timezone(&self, timezone: Tz) -> Period {
+        let range = self.time_range(timezone);
+        let last = range.end - TimeDelta::milliseconds(1);
+        Period::new(
+            range.start.date_naive(),
+            Some(last.date_naive()),
+            self.period_name(),
+        )
+    }
+}
+
+/// Midnight of a day in a timezone, or the first time of the day when a DST change skips midnight.
+fn start_of_day(date: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+    (0..24)
+        .find_map(|hour| {
+            let time = date.and_hms_opt(hour, 0, 0)?;
+            timezone.from_local_datetime(&time).earliest()
+        })
+        .expect("a day has atleast one hour")
+        .with_timezone(&Utc)
+}
+
+/// Errors when a [`Period`] covers files that can not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeriodError {
+    EndBeforeStart {
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    },
+    /// The start date is past the latest published file.
+    NotPublished {
+        period_name: PeriodName,
+        start_date: NaiveDate,
+        latest: NaiveDate,
+    },
+}
+
+impl Display for PeriodError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeriodError::EndBeforeStart {
+                start_date,
+                end_date,
+            } => write!(f, "end date {end_date} is before start date {start_date}"),
+            PeriodError::NotPublished {
+                period_name,
+                start_date,
+                latest,
+            } => {
+                let format = match period_name {
+                    PeriodName::Daily => "%Y-%m-%d",
+                    PeriodName::Monthly => "%Y-%m",
+                };
+                write!(
+                    f,
+                    "no {period_name} file has been published for {} yet, the latest is {}",
+                    start_date.format(format),
+                    latest.format(format),
+                )
+            }
+        }
+    }
 }
 
+impl std::error::Error for PeriodError {}
+
 impl Display for Period {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PeriodName {
     Daily,
     Monthly,
 }
 
+impl PeriodName {
+    /// Date of the latest file Binance has published, given today's date in UTC.
+    /// For monthly files this is the first of the month.
+    pub fn latest_published(&self, today: NaiveDate) -> NaiveDate {
+        match self {
+            PeriodName::Daily => today.pred_opt().expect("date out of bounds"),
+            PeriodName::Monthly => today
+                .with_day(1)
+                .and_then(|date| date.checked_sub_months(chrono::Months::new(1)))
+                .expect("date out of bounds"),
+        }
+    }
+}
+
 impl Display for PeriodName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -233,4 +590,124 @@ mod tests {
         assert_eq!(Some(nd(2025, 1, 1)), date_iter.next());
         assert_eq!(None, date_iter.next());
     }
+
+    #[test]
+    fn period_in_timezone() {
+        let utc = |date: NaiveDate, hour| date.and_hms_opt(hour, 0, 0).unwrap().and_utc();
+
+        let period = Period::new(nd(2025, 1, 1), Some(nd(2025, 1, 2)), PeriodName::Daily);
+        let range = period.time_range(Tz::UTC);
+        assert_eq!(utc(nd(2025, 1, 1), 0), range.start);
+        assert_eq!(utc(nd(2025, 1, 3), 0), range.end);
+        assert_eq!(Some(nd(2025, 1, 2)), period.in_timezone(Tz::UTC).end_date());
+
+        // UTC-5 in winter, so the last day ends in the next UTC day
+        let range = period.time_range(Tz::America__New_York);
+        assert_eq!(utc(nd(2025, 1, 1), 5), range.start);
+        let files = period.in_timezone(Tz::America__New_York);
+        assert_eq!(nd(2025, 1, 1), files.start_date());
+        assert_eq!(Some(nd(2025, 1, 3)), files.end_date());
+
+        // UTC+9, the first day starts in the previous UTC month
+        let period = Period::new(nd(2025, 1, 1), None, PeriodName::Monthly);
+        let files = period.in_timezone(Tz::Asia__Tokyo);
+        assert_eq!(nd(2024, 12, 1), files.start_date());
+        assert_eq!(Some(nd(2025, 1, 1)), files.end_date());
+    }
+
+    #[test]
+    fn validate_period() {
+        let today = nd(2025, 3, 10);
+
+        let period = Period::new(nd(2025, 1, 5), Some(nd(2025, 1, 1)), PeriodName::Daily);
+        assert!(matches!(
+            period.check_order(),
+            Err(PeriodError::EndBeforeStart { .. })
+        ));
+        assert!(matches!(
+            period.validate(today),
+            Err(PeriodError::EndBeforeStart { .. })
+        ));
+        let period = Period::new(nd(2025, 1, 1), None, PeriodName::Daily);
+        assert!(period.check_order().is_ok());
+
+        // the file of today is published tomorrow
+        let period = Period::new(nd(2025, 3, 10), None, PeriodName::Daily);
+        assert!(matches!(
+            period.validate(today),
+            Err(PeriodError::NotPublished { .. })
+        ));
+
+        let period = Period::new(nd(2025, 3, 1), Some(nd(2025, 12, 1)), PeriodName::Daily)
+            .validate(today)
+            .unwrap();
+        assert_eq!(Some(nd(2025, 3, 9)), period.end_date());
+
+        // march is not over yet
+        let period = Period::new(nd(2025, 1, 1), Some(nd(2025, 3, 1)), PeriodName::Monthly)
+            .validate(today)
+            .unwrap();
+        assert_eq!(Some(nd(2025, 2, 1)), period.end_date());
+
+        let period = Period::new(nd(2025, 3, 1), None, PeriodName::Monthly);
+        assert!(matches!(
+            period.validate(today),
+            Err(PeriodError::NotPublished { .. })
+        ));
+    }
+
+    #[test]
+    fn start_range_without_end() {
+        let parse = |args: &[&str]| {
+            let cmd = Period::augment_subcommands(clap::Command::new("cryptory"));
+            let matches = cmd.try_get_matches_from(args).unwrap();
+            Period::from_arg_matches(&matches).unwrap()
+        };
+
+        let period = parse(&["cryptory", "daily", "2024"]);
+        assert_eq!(366, DateIterator::from(period).count());
+
+        let period = parse(&["cryptory", "daily", "2024-Q3"]);
+        assert_eq!(nd(2024, 7, 1), period.start_date());
+        assert_eq!(Some(nd(2024, 9, 30)), period.end_date());
+
+        let period = parse(&["cryptory", "monthly", "2024"]);
+        assert_eq!(12, DateIterator::from(period).count());
+
+        // an end date still takes precedence
+        let period = parse(&["cryptory", "daily", "2024", "-e", "2024-01-02"]);
+        assert_eq!(2, DateIterator::from(period).count());
+
+        let period = parse(&["cryptory", "daily", "2024-01-05"]);
+        assert_eq!(1, DateIterator::from(period).count());
+    }
+
+    #[test]
+    fn date_expressions() {
+        let today = nd(2025, 3, 10);
+        let range = |input: &str| {
+            let expr = DateExpr::from_str(input).unwrap();
+            (expr.first(today).unwrap(), expr.last(today).unwrap())
+        };
+
+        assert_eq!((nd(2025, 1, 5), nd(2025, 1, 5)), range("2025-01-05"));
+        assert_eq!((today, today), range("today"));
+        assert_eq!((nd(2025, 3, 9), nd(2025, 3, 9)), range("Yesterday"));
+        assert_eq!((nd(2025, 2, 8), nd(2025, 2, 8)), range("-30d"));
+        assert_eq!((nd(2024, 9, 10), nd(2024, 9, 10)), range("-6mo"));
+        assert_eq!((nd(2025, 1, 1), today), range("ytd"));
+        assert_eq!((nd(2024, 1, 1), nd(2024, 12, 31)), range("2024"));
+        assert_eq!((nd(2024, 7, 1), nd(2024, 9, 30)), range("2024-Q3"));
+        assert_eq!((nd(2024, 2, 1), nd(2024, 2, 29)), range("2024-02"));
+
+        for input in ["", "-d", "-30", "2024-Q5", "2024-13", "24", "tomorrow"] {
+            assert!(DateExpr::from_str(input).is_err(), "{input} should fail");
+        }
+
+        // out of the range of dates
+        assert_eq!(None, DateExpr::DaysAgo(100_000_000).first(today));
+        assert_eq!(None, DateExpr::MonthsAgo(99_999_999).last(today));
+        assert!(parse_start_date("-100000000d").is_err());
+        assert!(parse_end_date("-99999999mo").is_err());
+    }
 }
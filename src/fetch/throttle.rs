@@ -0,0 +1,128 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Limits the combined download rate of all downloads sharing the same throttle.
+///
+/// Works as a token bucket that is allowed to go into debt,
+/// a download that takes more bytes than available waits until the debt is paid off.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    bytes_per_sec: u64,
+    /// Bytes that can be read right now, negative when in debt.
+    available: f64,
+    last_refill: Instant,
+}
+
+impl Throttle {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1);
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                bytes_per_sec,
+                available: bytes_per_sec as f64,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// Take bytes from the bucket, waiting if the limit has been reached.
+    pub async fn consume(&self, bytes: u64) {
+        let wait = {
+            let mut bucket = self.bucket.lock().expect("throttle lock poisoned");
+            bucket.refill(Instant::now());
+            bucket.available -= bytes as f64;
+            bucket.wait_time()
+        };
+
+        if let Some(wait) = wait {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        let rate = self.bytes_per_sec as f64;
+        // Never store more than one second worth of bytes, to keep bursts small.
+        self.available = (self.available + elapsed * rate).min(rate);
+        self.last_refill = now;
+    }
+
+    fn wait_time(&self) -> Option<Duration> {
+        if self.available >= 0.0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(
+            -self.available / self.bytes_per_sec as f64,
+        ))
+    }
+}
+
+/// Parse a rate in bytes per second, with an optional K, M or G suffix (powers of 1000).
+/// Example: 500K, 2M, 1.5M
+pub fn parse_rate(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let (number, multiplier) = match input.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&input[..input.len() - 1], 1e3),
+        Some('M') => (&input[..input.len() - 1], 1e6),
+        Some('G') => (&input[..input.len() - 1], 1e9),
+        _ => (input, 1.0),
+    };
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid rate {input}, expected e.g. 500K or 2M"))?;
+
+    let rate = (number * multiplier).round();
+    if !rate.is_finite() || rate < 1.0 {
+        return Err(format!(
+            "Invalid rate {input}, must be atleast 1 byte per second"
+        ));
+    }
+    Ok(rate as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rates() {
+        assert_eq!(Ok(100), parse_rate("100"));
+        assert_eq!(Ok(500_000), parse_rate("500K"));
+        assert_eq!(Ok(500_000), parse_rate("500k"));
+        assert_eq!(Ok(1_500_000), parse_rate("1.5M"));
+        assert_eq!(Ok(2_000_000_000), parse_rate("2G"));
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("").is_err());
+    }
+
+    #[test]
+    fn bucket_goes_into_debt() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            bytes_per_sec: 1000,
+            available: 1000.0,
+            last_refill: start,
+        };
+
+        bucket.available -= 3000.0;
+        assert_eq!(Some(Duration::from_secs(2)), bucket.wait_time());
+
+        bucket.refill(start + Duration::from_secs(2));
+        assert_eq!(None, bucket.wait_time());
+
+        // Idle time does not build up more than one second of bytes
+        bucket.refill(start + Duration::from_secs(60));
+        assert_eq!(1000.0, bucket.available);
+    }
+}
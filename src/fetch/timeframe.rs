@@ -1,23 +1,193 @@
+use chrono::{DateTime, Datelike, Months, TimeDelta, TimeZone, Utc};
+
 use std::{fmt::Display, str::FromStr};
 
 /// Valid representation of timeframes that can be fetched from binance.
-#[derive(Debug, Clone)]
-pub struct TimeFrame(String);
+///
+/// Ordered from the shortest to the longest timeframe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TimeFrame {
+    S1,
+    M1,
+    M3,
+    M5,
+    M15,
+    M30,
+    H1,
+    H2,
+    H4,
+    H6,
+    H8,
+    H12,
+    D1,
+    D3,
+    W1,
+    Mo1,
+}
 
-impl FromStr for TimeFrame {
-    type Err = &'static str;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "12h" | "15m" | "1d" | "1h" | "1m" | "1mo" | "1s" | "1w" | "2h" | "30m" | "3d"
-            | "3m" | "4h" | "5m" | "6h" | "8h" => Ok(TimeFrame(s.to_string())),
-            _ => Err("Invalid timeframe! Valid values are:\n 1s 1m 3m 5m 15m 30m 1h 2h 4h 6h 8h 12h 1d 1w 1mo")
+impl TimeFrame {
+    /// All timeframes, from the shortest to the longest.
+    pub const ALL: [TimeFrame; 16] = [
+        TimeFrame::S1,
+        TimeFrame::M1,
+        TimeFrame::M3,
+        TimeFrame::M5,
+        TimeFrame::M15,
+        TimeFrame::M30,
+        TimeFrame::H1,
+        TimeFrame::H2,
+        TimeFrame::H4,
+        TimeFrame::H6,
+        TimeFrame::H8,
+        TimeFrame::H12,
+        TimeFrame::D1,
+        TimeFrame::D3,
+        TimeFrame::W1,
+        TimeFrame::Mo1,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeFrame::S1 => "1s",
+            TimeFrame::M1 => "1m",
+            TimeFrame::M3 => "3m",
+            TimeFrame::M5 => "5m",
+            TimeFrame::M15 => "15m",
+            TimeFrame::M30 => "30m",
+            TimeFrame::H1 => "1h",
+            TimeFrame::H2 => "2h",
+            TimeFrame::H4 => "4h",
+            TimeFrame::H6 => "6h",
+            TimeFrame::H8 => "8h",
+            TimeFrame::H12 => "12h",
+            TimeFrame::D1 => "1d",
+            TimeFrame::D3 => "3d",
+            TimeFrame::W1 => "1w",
+            TimeFrame::Mo1 => "1mo",
+        }
+    }
+
+    /// Length of a bar, None for 1mo since months differ in length.
+    pub fn duration(&self) -> Option<TimeDelta> {
+        let duration = match self {
+            TimeFrame::S1 => TimeDelta::seconds(1),
+            TimeFrame::M1 => TimeDelta::minutes(1),
+            TimeFrame::M3 => TimeDelta::minutes(3),
+            TimeFrame::M5 => TimeDelta::minutes(5),
+            TimeFrame::M15 => TimeDelta::minutes(15),
+            TimeFrame::M30 => TimeDelta::minutes(30),
+            TimeFrame::H1 => TimeDelta::hours(1),
+            TimeFrame::H2 => TimeDelta::hours(2),
+            TimeFrame::H4 => TimeDelta::hours(4),
+            TimeFrame::H6 => TimeDelta::hours(6),
+            TimeFrame::H8 => TimeDelta::hours(8),
+            TimeFrame::H12 => TimeDelta::hours(12),
+            TimeFrame::D1 => TimeDelta::days(1),
+            TimeFrame::D3 => TimeDelta::days(3),
+            TimeFrame::W1 => TimeDelta::weeks(1),
+            TimeFrame::Mo1 => return None,
+        };
+        Some(duration)
+    }
+
+    /// Number of bars in a day, fractional for timeframes longer than a day.
+    /// 1mo uses the average length of a month.
+    pub fn bars_per_day(&self) -> f64 {
+        const SECONDS_PER_DAY: f64 = 86_400.0;
+        match self.duration() {
+            Some(duration) => SECONDS_PER_DAY / duration.num_seconds() as f64,
+            None => 12.0 / 365.25,
+        }
+    }
+
+    /// Open time of the bar the timestamp falls in.
+    ///
+    /// Bars are aligned to the unix epoch like binance does,
+    /// except weekly bars which open on monday and monthly bars which open on the 1st.
+    pub fn align(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            TimeFrame::Mo1 => Utc
+                .with_ymd_and_hms(timestamp.year(), timestamp.month(), 1, 0, 0, 0)
+                .single()
+                .expect("1st of the month is a valid date"),
+            TimeFrame::W1 => {
+                // the epoch is a thursday, so weeks are offset by 4 days
+                let offset = TimeDelta::days(4);
+                align_to(timestamp - offset, TimeDelta::weeks(1)) + offset
+            }
+            _ => align_to(
+                timestamp,
+                self.duration().expect("only 1mo has no duration"),
+            ),
+        }
+    }
 
+    /// Open time of the bar after the bar the timestamp falls in.
+    pub fn next_open(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let open = self.align(timestamp);
+        match self.duration() {
+            Some(duration) => open + duration,
+            None => open
+                .checked_add_months(Months::new(1))
+                .expect("date out of bounds"),
         }
     }
 }
 
+fn align_to(timestamp: DateTime<Utc>, duration: TimeDelta) -> DateTime<Utc> {
+    let millis = duration.num_milliseconds();
+    let aligned = timestamp.timestamp_millis().div_euclid(millis) * millis;
+    DateTime::from_timestamp_millis(aligned).expect("aligned timestamp is in range")
+}
+
+impl FromStr for TimeFrame {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        TimeFrame::ALL
+            .into_iter()
+            .find(|timeframe| timeframe.as_str() == s)
+            .ok_or_else(|| {
+                let valid: Vec<&str> = TimeFrame::ALL.iter().map(TimeFrame::as_str).collect();
+                format!("Invalid timeframe! Valid values are:\n {}", valid.join(" "))
+            })
+    }
+}
+
 impl Display for TimeFrame {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(input: &str) -> DateTime<Utc> {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_order() {
+        assert_eq!(Ok(TimeFrame::D3), "3d".parse());
+        assert_eq!(Ok(TimeFrame::H1), "1H".parse());
+        assert!("2d".parse::<TimeFrame>().unwrap_err().contains(" 3d "));
+        assert!(TimeFrame::M5 < TimeFrame::H1);
+        assert!(TimeFrame::W1 < TimeFrame::Mo1);
+        assert_eq!(24.0, TimeFrame::H1.bars_per_day());
+    }
+
+    #[test]
+    fn align_timestamps() {
+        let time = ts("2025-01-15T13:47:12Z");
+        assert_eq!(ts("2025-01-15T13:45:00Z"), TimeFrame::M15.align(time));
+        assert_eq!(ts("2025-01-15T12:00:00Z"), TimeFrame::H4.align(time));
+        assert_eq!(ts("2025-01-15T00:00:00Z"), TimeFrame::D1.align(time));
+        // monday
+        assert_eq!(ts("2025-01-13T00:00:00Z"), TimeFrame::W1.align(time));
+        assert_eq!(ts("2025-01-01T00:00:00Z"), TimeFrame::Mo1.align(time));
+        assert_eq!(ts("2025-02-01T00:00:00Z"), TimeFrame::Mo1.next_open(time));
+        assert_eq!(ts("2025-01-15T14:00:00Z"), TimeFrame::H1.next_open(time));
     }
 }
@@ -0,0 +1,373 @@
+use crate::fetch::{
+    download_file, parse_base_url, ClientError, ClientOptions, Msg, PeriodError, DEFAULT_BASE_URL,
+};
+use crate::{
+    DataType, FileInfo, FileInfoIterator, Market, Period, PeriodName, Throttle, Ticker, TimeFrame,
+};
+
+use futures_util::Stream;
+use reqwest::Url;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A set of files to download, and the settings used to download them.
+///
+/// Created with [`DownloadJob::builder`].
+///
+/// Clones share the same cancellation, cancelling one cancels all of them.
+#[derive(Debug, Clone)]
+pub struct DownloadJob {
+    files: Files,
+    client: reqwest::Client,
+    overwrite: bool,
+    throttle: Option<Throttle>,
+    cancel: CancellationToken,
+}
+
+#[derive(Debug, Clone)]
+enum Files {
+    Period {
+        tickers: Vec<Ticker>,
+        market: Market,
+        data_type: DataType,
+        timeframes: Vec<TimeFrame>,
+        period: Period,
+        hosts: Vec<Url>,
+        output_dir: PathBuf,
+    },
+    List(Vec<FileInfo>),
+}
+
+impl DownloadJob {
+    pub fn builder() -> DownloadJobBuilder {
+        DownloadJobBuilder::default()
+    }
+
+    /// The files this job downloads, in the order they are started.
+    pub fn files(&self) -> Vec<FileInfo> {
+        match &self.files {
+            Files::Period {
+                tickers,
+                market,
+                data_type,
+                timeframes,
+                period,
+                hosts,
+                output_dir,
+            } => FileInfoIterator::new(tickers.clone(), timeframes.clone(), period.clone())
+                .with_market(*market)
+                .with_data_type(*data_type)
+                .with_hosts(hosts.clone())
+                .with_output_dir(output_dir.clone())
+                .collect(),
+            Files::List(files) => files.clone(),
+        }
+    }
+
+    /// The period of the files after it was validated, the end date is clamped to
+    /// the latest file published. None when the files were given directly.
+    pub fn period(&self) -> Option<&Period> {
+        match &self.files {
+            Files::Period { period, .. } => Some(period),
+            Files::List(_) => None,
+        }
+    }
+
+    /// The http client used for all requests of this job.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Start downloading all files of the job.
+    ///
+    /// Each file is downloaded in its own task, so this has to be called within a tokio runtime.
+    pub fn start(&self) -> Progress {
+        self.download(self.files())
+    }
+
+    /// Download the given files with the settings of this job,
+    /// for example to retry files that failed.
+    pub fn download(&self, files: impl IntoIterator<Item = FileInfo>) -> Progress {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        for fileinfo in files {
+            tokio::spawn(download_file(
+                fileinfo,
+                self.client.clone(),
+                tx.clone(),
+                self.overwrite,
+                self.throttle.clone(),
+                self.cancel.clone(),
+            ));
+        }
+
+        Progress { rx }
+    }
+
+    /// Stop the downloads of this job.
+    ///
+    /// Files that have not started fail with [`Error::Cancelled`](crate::Error::Cancelled),
+    /// and files being downloaded are removed before they fail the same way.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+}
+
+/// Stream of progress updates from the files being downloaded.
+///
+/// Every file sends [`MsgType::Starting`](crate::MsgType::Starting) followed by
+/// [`MsgType::Written`](crate::MsgType::Written) updates, and ends with either
+/// [`MsgType::Done`](crate::MsgType::Done) or [`MsgType::Error`](crate::MsgType::Error).
+/// Files that fail before they start only send the error.
+/// The stream ends when all files are finished.
+#[derive(Debug)]
+pub struct Progress {
+    rx: mpsc::UnboundedReceiver<Msg>,
+}
+
+impl Progress {
+    /// Receive the next update, None when all files are finished.
+    pub async fn recv(&mut self) -> Option<Msg> {
+        self.rx.recv().await
+    }
+}
+
+impl Stream for Progress {
+    type Item = Msg;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Builder for a [`DownloadJob`].
+///
+/// Tickers, timeframes (for klines) and period are required unless the files are given directly,
+/// everything else has defaults.
+#[derive(Debug, Default)]
+pub struct DownloadJobBuilder {
+    files: Vec<FileInfo>,
+    tickers: Vec<Ticker>,
+    market: Market,
+    data_type: DataType,
+    timeframes: Vec<TimeFrame>,
+    period: Option<Period>,
+    hosts: Vec<Url>,
+    output_dir: Option<PathBuf>,
+    client: Option<reqwest::Client>,
+    client_options: ClientOptions,
+    overwrite: bool,
+    limit_rate: Option<u64>,
+}
+
+impl DownloadJobBuilder {
+    /// Download these files instead of the files from tickers, timeframe and period,
+    /// for example the files of a [`ResumeFile`](crate::ResumeFile).
+    /// Hosts and output dir are ignored since the files already have them.
+    pub fn files(mut self, files: impl IntoIterator<Item = FileInfo>) -> Self {
+        self.files.extend(files);
+        self
+    }
+
+    /// Tickers that were already added are skipped, so no file is downloaded twice.
+    pub fn ticker(mut self, ticker: Ticker) -> Self {
+        if !self.tickers.contains(&ticker) {
+            self.tickers.push(ticker);
+        }
+        self
+    }
+
+    pub fn tickers(self, tickers: impl IntoIterator<Item = Ticker>) -> Self {
+        tickers.into_iter().fold(self, Self::ticker)
+    }
+
+    /// Market to download from, defaults to spot.
+    pub fn market(mut self, market: Market) -> Self {
+        self.market = market;
+        self
+    }
+
+    /// Kind of data to download, defaults to klines.
+    /// Timeframes are only needed for klines.
+    pub fn data_type(mut self, data_type: DataType) -> Self {
+        self.data_type = data_type;
+        self
+    }
+
+    /// Timeframes that were already added are skipped, so no file is downloaded twice.
+    pub fn timeframe(mut self, timeframe: TimeFrame) -> Self {
+        if !self.timeframes.contains(&timeframe) {
+            self.timeframes.push(timeframe);
+        }
+        self
+    }
+
+    pub fn timeframes(self, timeframes: impl IntoIterator<Item = TimeFrame>) -> Self {
+        timeframes.into_iter().fold(self, Self::timeframe)
+    }
+
+    /// Period of the files, it is checked against the files published so far when the job is built,
+    /// see [`Period::validate`].
+    pub fn period(mut self, period: Period) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    /// Add a host to download from, the first host added is tried first
+    /// and the rest are used as fallbacks in the order they were added.
+    /// Defaults to https://data.binance.vision/
+    pub fn host(mut self, host: Url) -> Self {
+        self.hosts.push(host);
+        self
+    }
+
+    pub fn hosts(mut self, hosts: impl IntoIterator<Item = Url>) -> Self {
+        self.hosts.extend(hosts);
+        self
+    }
+
+    /// Directory the files are written to, defaults to the current directory.
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(output_dir.into());
+        self
+    }
+
+    /// Use this client for all requests, client_options are ignored when a client is given.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Options used to build the http client.
+    pub fn client_options(mut self, client_options: ClientOptions) -> Self {
+        self.client_options = client_options;
+        self
+    }
+
+    /// Overwrite files that already exist, by default they fail with
+    /// [`Error::CouldNotOpenFile`](crate::Error::CouldNotOpenFile).
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Max combined download rate of all files in bytes per second.
+    pub fn limit_rate(mut self, bytes_per_sec: u64) -> Self {
+        self.limit_rate = Some(bytes_per_sec);
+        self
+    }
+
+    pub fn build(self) -> Result<DownloadJob, JobError> {
+        let files = if self.files.is_empty() {
+            if self.tickers.is_empty() {
+                return Err(JobError::NoTickers);
+            }
+            if self.data_type.is_futures_only() && !self.market.is_futures() {
+                return Err(JobError::FuturesOnly(self.data_type));
+            }
+            if self.data_type.has_timeframe() && self.timeframes.is_empty() {
+                return Err(JobError::NoTimeFrame);
+            }
+            let period = self
+                .period
+                .ok_or(JobError::NoPeriod)?
+                .validate(chrono::Utc::now().date_naive())
+                .map_err(JobError::InvalidPeriod)?;
+            if !self.data_type.is_published(period.period_name()) {
+                return Err(JobError::NotPublished {
+                    data_type: self.data_type,
+                    period_name: period.period_name(),
+                });
+            }
+
+            let hosts = if self.hosts.is_empty() {
+                vec![parse_base_url(DEFAULT_BASE_URL).expect("valid default url")]
+            } else {
+                self.hosts
+            };
+
+            let output_dir = match self.output_dir {
+                Some(output_dir) => output_dir,
+                None => std::env::current_dir().map_err(JobError::NoOutputDir)?,
+            };
+
+            Files::Period {
+                tickers: self.tickers,
+                market: self.market,
+                data_type: self.data_type,
+                timeframes: self.timeframes,
+                period,
+                hosts,
+                output_dir,
+            }
+        } else {
+            Files::List(self.files)
+        };
+
+        let client = match self.client {
+            Some(client) => client,
+            None => self
+                .client_options
+                .build()
+                .map_err(JobError::InvalidClientOptions)?,
+        };
+
+        Ok(DownloadJob {
+            files,
+            client,
+            overwrite: self.overwrite,
+            throttle: self.limit_rate.map(Throttle::new),
+            cancel: CancellationToken::new(),
+        })
+    }
+}
+
+/// Errors when building a [`DownloadJob`].
+#[derive(Debug)]
+pub enum JobError {
+    NoTickers,
+    NoTimeFrame,
+    /// The data type is only published for futures, but the market is spot.
+    FuturesOnly(DataType),
+    NoPeriod,
+    InvalidPeriod(PeriodError),
+    /// The data type is not published for the period, e.g. daily funding rates.
+    NotPublished {
+        data_type: DataType,
+        period_name: PeriodName,
+    },
+    /// No output dir was given and the current directory could not be read.
+    NoOutputDir(std::io::Error),
+    InvalidClientOptions(ClientError),
+}
+
+impl Display for JobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobError::NoTickers => write!(f, "you must provide atleast one ticker"),
+            JobError::NoTimeFrame => write!(f, "you must provide atleast one timeframe"),
+            JobError::FuturesOnly(data_type) => {
+                write!(f, "{data_type} are only published for futures markets")
+            }
+            JobError::NoPeriod => write!(f, "you must provide a period"),
+            JobError::InvalidPeriod(e) => write!(f, "invalid period: {e}"),
+            JobError::NotPublished {
+                data_type,
+                period_name,
+            } => write!(f, "no {period_name} {data_type} files are published"),
+            JobError::NoOutputDir(e) => write!(f, "could not open current directory: {e}"),
+            JobError::InvalidClientOptions(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
@@ -0,0 +1,56 @@
+//! Cryptory, fetch historical data for cryptocurrencies from
+//! Binance public data <https://data.binance.vision/>.
+//!
+//! Build a [`DownloadJob`] with the tickers, timeframes and period you want,
+//! then start it and follow the progress of each file.
+//!
+//! ```no_run
+//! use cryptory::{DownloadJob, MsgType, Period};
+//! use futures_util::StreamExt;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let job = DownloadJob::builder()
+//!     .ticker("btcusdt".parse()?)
+//!     .timeframe("1h".parse()?)
+//!     .period(Period::Daily {
+//!         start_date: "2025-01-01".parse()?,
+//!         end_date: Some("2025-01-31".parse()?),
+//!     })
+//!     .output_dir("data")
+//!     .build()?;
+//!
+//! let mut progress = job.start();
+//! while let Some(msg) = progress.next().await {
+//!     if let MsgType::Error { fileinfo, error } = msg.msg_type {
+//!         eprintln!("{} failed: {error}", fileinfo.file_name());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Instead of handling the updates yourself you can pass a [`Reporter`]
+//! to [`Progress::report_to`], which also collects a [`Summary`] of the failed files.
+
+mod data;
+mod fetch;
+mod job;
+mod report;
+mod resume;
+mod ticker;
+
+pub use data::{
+    enrich, read_rows, reconcile, to_datetime, BarBuilder, BarKind, BookDepth, DataError,
+    DepthLevel, DepthSnapshot, Distribution, EnrichedKline, FieldDiff, Fill, FundingRate, Gap,
+    Kline, KlineStats, Liquidity, Mismatch, Panel, PanelValue, TimeFormat, TimeOutput, TimeUnit,
+    Trade,
+};
+pub use fetch::{
+    download_file, parse_base_url, parse_end_date, parse_rate, parse_start_date, ClientError,
+    ClientOptions, DataType, DateExpr, Error, FileInfo, FileInfoIterator, Market, Msg, MsgType,
+    Period, PeriodError, PeriodName, Throttle, TimeFrame, DEFAULT_BASE_URL,
+};
+pub use job::{DownloadJob, DownloadJobBuilder, JobError, Progress};
+pub use report::{Reporter, Silent, Summary};
+pub use resume::ResumeFile;
+pub use ticker::{Ticker, Tickerator};
@@ -0,0 +1,35 @@
+use cryptory::{Msg, MsgType, Reporter};
+use indicatif::DecimalBytes;
+
+use std::collections::HashMap;
+
+/// Plain log lines instead of progress bars, one line when a file starts and when it ends.
+/// Useful when the output is not a terminal, e.g. in CI or when piped to a file.
+#[derive(Default)]
+pub struct Log {
+    names: HashMap<usize, String>,
+}
+
+impl Reporter for Log {
+    fn report(&mut self, msg: &Msg) {
+        match &msg.msg_type {
+            MsgType::Starting { name, total_size } => {
+                let size = total_size
+                    .map(|s| DecimalBytes(s).to_string())
+                    .unwrap_or_else(|| "unknown size".to_string());
+                println!("Downloading {name} ({size})");
+                self.names.insert(msg.file_id, name.clone());
+            }
+            MsgType::Written { .. } => (),
+            MsgType::Done => {
+                if let Some(name) = self.names.remove(&msg.file_id) {
+                    println!("Done {name}");
+                }
+            }
+            MsgType::Error { fileinfo, error } => {
+                self.names.remove(&msg.file_id);
+                eprintln!("Failed {}: {error}", fileinfo.file_name());
+            }
+        }
+    }
+}
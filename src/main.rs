@@ -1,160 +1,570 @@
-mod fetch;
-mod ticker;
+mod build_bars;
+mod config;
+mod depth;
+mod downloaded;
+mod dry_run;
+mod enrich;
+mod events;
+mod log;
+mod panel;
+mod query;
+mod reconcile;
+mod serve;
+mod stats;
+#[cfg(test)]
+#[path = "data/test_klines.rs"]
+mod test_klines;
+mod ticker_args;
+mod time_args;
 mod user_input;
 
-// testing
-use fetch::*;
-pub use ticker::Ticker;
+use cryptory::*;
+use ticker_args::{read_required_tickers, TickerArg};
 use user_input::*;
 
 mod progress_bars;
 use progress_bars::ProgressBars;
 
-use clap::Parser;
-use tokio::sync::mpsc;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use config::{Config, ConfigError};
+use reqwest::Url;
 
-use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Exit codes so scripts can tell how a run went.
+/// 2 is left out since it is used by clap for invalid arguments.
+mod exit_code {
+    pub const SUCCESS: i32 = 0;
+    pub const INVALID_INPUT: i32 = 1;
+    /// Some files were downloaded but others failed.
+    pub const PARTIAL_FAILURE: i32 = 3;
+    /// None of the files could be downloaded.
+    pub const TOTAL_FAILURE: i32 = 4;
+    /// Reconcile found klines that do not match the trades.
+    pub const MISMATCH: i32 = 5;
+    /// Stopped with Ctrl-C, same as shells use for SIGINT.
+    pub const INTERRUPTED: i32 = 130;
+
+    /// Exit code of a download of `total` files, files that already exist are skipped
+    /// and neither count as downloaded nor as failed.
+    pub fn of_downloads(total: usize, failed: usize, skipped: usize) -> i32 {
+        match failed {
+            0 => SUCCESS,
+            failed if failed >= total.saturating_sub(skipped) => TOTAL_FAILURE,
+            _ => PARTIAL_FAILURE,
+        }
+    }
+}
+
+/// Where the files that were not downloaded are saved when interrupted.
+const DEFAULT_RESUME_FILE: &str = "cryptory-resume.json";
 
 #[tokio::main]
 async fn main() {
-    let input = Input::parse();
+    let matches = Input::command().get_matches();
+    let input = Input::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    match &input.command {
+        Some(Command::Period(_)) | None => (),
+        Some(command) => {
+            reject_download_args(&matches);
+            if let Err(e) = run_command(command).await {
+                eprintln!("ERROR: {e}");
+                std::process::exit(exit_code::INVALID_INPUT)
+            }
+            return;
+        }
+    }
+
+    let job = match download_job(&input) {
+        Ok(job) => job,
+        Err(e) => {
+            eprintln!("ERROR: {e}");
+            std::process::exit(exit_code::INVALID_INPUT)
+        }
+    };
 
-    if input.ticker.is_empty() {
-        eprintln!("ERROR: you must provide atleast one ticker");
-        std::process::exit(1)
+    if input.dry_run {
+        dry_run::dry_run(
+            job.files(),
+            job.client().clone(),
+            input.overwrite,
+            input.json,
+        )
+        .await;
+        return;
     }
 
-    let overwrite = input.overwrite;
-    let mut rx = download_files(FileInfoIterator::from(input), overwrite);
+    handle_ctrl_c(job.clone());
+
+    let output = input.output();
+    let mut progress = job.start();
+    let mut files_in_round = job.files().len();
+
+    let mut total_files = None;
+    // files that failed and were not retried
+    let mut given_up = 0;
+    // files that already exist and were not overwritten
+    let mut skipped = 0;
+
+    // files that failed in the last round, kept for the resume file
+    let (failed, errors) = loop {
+        let Summary { finished, errors } = progress
+            .report_to(&mut *output.reporter(files_in_round))
+            .await;
+        total_files.get_or_insert(finished + errors.len());
+        let existing = errors.iter().filter(|(_, e)| e.already_exists()).count();
+        skipped += existing;
 
-    loop {
-        if let Err(errors) = handle_file_updates(rx, true).await {
+        if job.is_cancelled() {
+            save_outstanding(&input, errors);
+            std::process::exit(exit_code::INTERRUPTED)
+        }
+
+        if errors.len() == existing {
+            if matches!(output, Output::Bars | Output::Log) {
+                println!("\nDone downloading files!");
+                if existing > 0 {
+                    println!("{existing} file(s) already exist and were skipped, -o to overwrite");
+                }
+            }
+            break (given_up, errors);
+        }
+
+        if matches!(output, Output::Bars | Output::Log) {
             println!("\nDone downloading files, but errors occured:");
-            let mut possible_retry = false;
-            // add files that failed to download, invalid name or already exist should not be
-            // parsed again
-
-            for (fileinfo, e) in errors.iter() {
-                let extra = match e {
-                    Error::CouldNotOpenFile(ErrorKind::AlreadyExists) => " -o to overwrite",
-                    Error::CouldNotFindFileAtHost => " Is symbol and date correct?",
-                    _ => {
-                        possible_retry = true;
-                        ""
-                    }
-                };
+        }
+
+        let mut possible_retry = false;
+        // add files that failed to download, invalid name or already exist should not be
+        // parsed again
+        for (fileinfo, e) in errors.iter() {
+            let extra = match e {
+                e if e.already_exists() => " -o to overwrite",
+                Error::CouldNotFindFileAtHost => " Is symbol and date correct?",
+                _ => {
+                    possible_retry = true;
+                    ""
+                }
+            };
+            if output != Output::Json {
                 eprintln!("{} Failed with error: {e}.{extra}", fileinfo.file_name());
             }
+        }
+
+        // only ask when there is someone to answer
+        if !possible_retry || output != Output::Bars {
+            break (given_up + errors.len() - existing, errors);
+        }
 
-            if !possible_retry {
-                break;
+        write_to_user("Do you want to retry downloading the other files that failed, y/n? ").await;
+        match user_input_yes_or_no().await {
+            UserInput::Yes => {
+                let (retry, skip): (Vec<_>, Vec<_>) =
+                    errors.into_iter().partition(|(_, e)| should_retry(e));
+                given_up += skip.len() - existing;
+                files_in_round = retry.len();
+                progress = job.download(retry.into_iter().map(|(file_info, _)| file_info));
+                continue;
             }
+            UserInput::InvalidInput | UserInput::NotExpectedInput | UserInput::No => {
+                break (given_up + errors.len() - existing, errors)
+            }
+        }
+    };
 
-            write_to_user("Do you want to retry downloading the other files that failed, y/n? ").await;
-            match user_input_yes_or_no().await {
-                UserInput::Yes => {
-                    // Filter out errors that should not be retried
-                    let overwrite_filter = |(_, e): &(FileInfo, Error)| match e {
-                        Error::CouldNotOpenFile(ErrorKind::AlreadyExists) => false,
-                        Error::CouldNotFindFileAtHost => false,
-                        _ => true,
-                    };
-                    rx = download_files(
-                        errors.into_iter().filter(overwrite_filter).map(|(file_info, _)| file_info),
-                        overwrite,
-                    );
-                    continue;
-                }
-                UserInput::InvalidInput => break,
-                UserInput::NotExpectedInput => break,
-                UserInput::No => break,
+    if let Some(resume_file) = &input.resume {
+        update_resume_file(resume_file, errors);
+    }
+
+    let total = total_files.unwrap_or_default();
+    if output == Output::Json {
+        events::Event::Summary {
+            total,
+            failed,
+            skipped,
+        }
+        .print();
+    }
+
+    std::process::exit(exit_code::of_downloads(total, failed, skipped))
+}
+
+/// The commands working on downloaded files take their own arguments,
+/// exit like clap does when arguments of downloads were given with them.
+fn reject_download_args(matches: &ArgMatches) {
+    let mut cmd = Input::command();
+    // arguments are only shown with their values once built
+    cmd.build();
+    let given = cmd
+        .get_arguments()
+        .find(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine));
+    if let (Some(arg), Some((name, _))) = (given, matches.subcommand()) {
+        let msg = format!(
+            "the argument '{arg}' can not be used with '{name}', it is only used for downloads"
+        );
+        cmd.error(clap::error::ErrorKind::ArgumentConflict, msg)
+            .exit()
+    }
+}
+
+/// Run a command working on downloaded files.
+async fn run_command(command: &Command) -> Result<(), String> {
+    match command {
+        Command::Period(_) => unreachable!("periods are downloaded by main"),
+        Command::Bars(args) => build_bars::build_bars(args),
+        Command::Reconcile(args) => {
+            if !reconcile::run_reconcile(args)? {
+                std::process::exit(exit_code::MISMATCH)
             }
-        } else {
-            println!("\nDone downloading files!");
-            break;
+            Ok(())
         }
+        Command::Panel(args) => panel::export_panel(args),
+        Command::Enrich(args) => enrich::export_enriched(args),
+        Command::Depth(args) => depth::run_depth(args),
+        Command::Query(args) => query::run_query(args),
+        Command::Stats(args) => stats::print_stats(args),
+        Command::Serve(args) => serve::serve(args).await,
+    }
+}
+
+/// Cancel the job on the first Ctrl-C, and quit right away on the second.
+fn handle_ctrl_c(job: DownloadJob) {
+    // the same listener has to be used for both, a new listener
+    // can be triggered by the signal that triggered the first one.
+    #[cfg(unix)]
+    let signal = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt());
+    #[cfg(windows)]
+    let signal = tokio::signal::windows::ctrl_c();
+
+    let Ok(mut signal) = signal else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        signal.recv().await;
+        eprintln!("\nStopping downloads, press Ctrl-C again to quit immediately");
+        job.cancel();
+
+        signal.recv().await;
+        std::process::exit(exit_code::INTERRUPTED)
+    });
+}
+
+/// Files that failed because they already exist or are missing at the host will fail again.
+fn should_retry(error: &Error) -> bool {
+    !(error.already_exists() || matches!(error, Error::CouldNotFindFileAtHost))
+}
+
+/// The files that failed in a way a later run could fix.
+fn outstanding(errors: Vec<(FileInfo, Error)>) -> Vec<FileInfo> {
+    errors
+        .into_iter()
+        .filter(|(_, e)| should_retry(e))
+        .map(|(fileinfo, _)| fileinfo)
+        .collect()
+}
+
+/// Save the files that were not downloaded when interrupted, so they can be resumed later.
+fn save_outstanding(input: &Input, errors: Vec<(FileInfo, Error)>) {
+    let outstanding = outstanding(errors);
+    if outstanding.is_empty() {
+        return;
+    }
+
+    let path = input
+        .resume
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_RESUME_FILE));
+
+    match ResumeFile::new(outstanding).write(&path) {
+        Ok(()) => eprintln!(
+            "Interrupted, continue with: cryptory --resume {}",
+            path.display()
+        ),
+        Err(e) => eprintln!("ERROR: could not write resume file {}: {e}", path.display()),
     }
 }
 
+/// Keep the files of a resumed run that failed and could still be downloaded,
+/// the resume file is removed when the others are done or failed in a way that resuming won't fix.
+fn update_resume_file(path: &Path, errors: Vec<(FileInfo, Error)>) {
+    let outstanding = outstanding(errors);
+    if outstanding.is_empty() {
+        let _ = std::fs::remove_file(path);
+        return;
+    }
+
+    match ResumeFile::new(outstanding).write(path) {
+        Ok(()) => eprintln!(
+            "Files that failed are kept, retry with: cryptory --resume {}",
+            path.display()
+        ),
+        Err(e) => eprintln!("ERROR: could not write resume file {}: {e}", path.display()),
+    }
+}
+
+/// Read the config file and combine it with the arguments into the job to run.
+fn download_job(input: &Input) -> Result<DownloadJob, String> {
+    let config = Config::load(input.config.as_deref()).map_err(|e| e.to_string())?;
+
+    let mut builder = DownloadJob::builder();
+    // a resumed run has its files instead of tickers
+    if input.resume.is_none() {
+        builder = builder.tickers(read_required_tickers(&input.ticker)?);
+    }
+
+    builder = builder
+        .market(input.market)
+        .data_type(input.data)
+        .timeframes(input.timeframe.iter().cloned());
+    if let Some(period) = input.period() {
+        builder = builder.period(period.clone());
+    }
+    if let Some(resume_file) = &input.resume {
+        if input.period().is_some() {
+            return Err("a period can not be used together with --resume".to_string());
+        }
+        let resume_file = ResumeFile::read(resume_file)
+            .map_err(|e| format!("could not read resume file {}: {e}", resume_file.display()))?;
+        builder = builder.files(resume_file.files);
+    }
+
+    let mut builder = builder
+        .hosts(input.hosts(&config).map_err(|e| e.to_string())?)
+        .client_options(input.client_options(&config).map_err(|e| e.to_string())?)
+        .overwrite(input.overwrite);
+
+    let limit_rate = match input.limit_rate {
+        Some(limit_rate) => Some(limit_rate),
+        None => config.limit_rate().map_err(|e| e.to_string())?,
+    };
+    if let Some(limit_rate) = limit_rate {
+        builder = builder.limit_rate(limit_rate);
+    }
+
+    let job = builder.build().map_err(|e| e.to_string())?;
+    if let (Some(period), Some(validated)) = (input.period(), job.period()) {
+        if validated.end_date() != period.end_date().or(Some(period.start_date())) {
+            eprintln!(
+                "NOTE: end date moved to {}, the latest file published",
+                validated
+                    .end_date()
+                    .expect("validated period has an end date")
+            );
+        }
+    }
+    Ok(job)
+}
+
 #[derive(Debug, Parser)]
 #[command(
     about = "\nCryptory\nUnofficial CLI for Binance public data\nMore information can be found on https://github.com/binance/binance-public-data/"
 )]
 struct Input {
     /// The ticker symbol you want to fetch data for.
-    ticker: Vec<Ticker>,
+    /// Use @FILE to read tickers from a file or - to read them from stdin,
+    /// one per line with # for comments.
+    #[arg(value_name = "TICKER")]
+    ticker: Vec<TickerArg>,
 
-    /// The timeframe of the bars to fetch
-    #[arg(short, long)]
-    timeframe: TimeFrame,
+    /// The timeframe of the bars to fetch, multiple can be given as e.g. 1m,1h,1d
+    #[arg(short, long, value_delimiter = ',')]
+    timeframe: Vec<TimeFrame>,
+
+    /// Kind of data to fetch, timeframes are only used for klines
+    #[arg(long, value_enum, default_value_t = DataType::Klines)]
+    data: DataType,
+
+    /// Market to fetch data from, mark and index prices and funding rates are only published for futures
+    #[arg(long, value_enum, default_value_t = Market::Spot)]
+    market: Market,
 
-    /// Period of the fetched file.
     #[command(subcommand)]
-    period: Period,
+    command: Option<Command>,
+
+    /// Continue downloading the files of an interrupted run
+    #[arg(long, value_name = "RESUME_FILE", conflicts_with_all = ["ticker", "timeframe", "data", "market"])]
+    resume: Option<PathBuf>,
 
     /// Force overwriting of files if they already exist
     #[arg(short, long)]
     overwrite: bool,
+
+    /// List the files that would be downloaded and their estimated size, without downloading
+    #[arg(long)]
+    dry_run: bool,
+
+    /// How to show the progress of the downloads.
+    /// Only bars asks to retry files that failed
+    #[arg(long, value_enum, default_value_t = Output::Bars)]
+    reporter: Output,
+
+    /// Print json lines, same as --reporter json.
+    /// Together with --dry-run one line per file is printed.
+    #[arg(long, conflicts_with_all = ["quiet", "reporter"])]
+    json: bool,
+
+    /// Only print errors, same as --reporter quiet
+    #[arg(short, long, conflicts_with = "reporter")]
+    quiet: bool,
+
+    /// Host to download the files from [default: https://data.binance.vision/]
+    #[arg(long, env = "CRYPTORY_BASE_URL", value_parser = parse_base_url)]
+    base_url: Option<Url>,
+
+    /// Fallback host tried if a file could not be downloaded, can be given multiple times.
+    /// Mirrors are tried in the order they are given
+    #[arg(long = "mirror", env = "CRYPTORY_MIRRORS", value_delimiter = ',', value_parser = parse_base_url)]
+    mirrors: Vec<Url>,
+
+    /// Path to the config file [default: ~/.config/cryptory/config.toml]
+    #[arg(long, env = "CRYPTORY_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Proxy used for all requests, e.g. http://proxy.internal:3128
+    #[arg(long, env = "CRYPTORY_PROXY")]
+    proxy: Option<Url>,
+
+    /// Pem file with extra root certificates to trust
+    #[arg(long, env = "CRYPTORY_CA_BUNDLE")]
+    ca_bundle: Option<PathBuf>,
+
+    /// Seconds to wait for a connection to be established
+    #[arg(long, value_name = "SECONDS")]
+    connect_timeout: Option<u64>,
+
+    /// Seconds to wait for data before giving up on a stalled connection
+    #[arg(long, value_name = "SECONDS")]
+    read_timeout: Option<u64>,
+
+    /// User agent sent with each request
+    #[arg(long)]
+    user_agent: Option<String>,
+
+    /// Max combined download rate of all files in bytes per second, e.g. 500K or 2M
+    #[arg(long, value_parser = parse_rate)]
+    limit_rate: Option<u64>,
 }
 
-impl From<Input> for FileInfoIterator {
-    fn from(value: Input) -> Self {
-        FileInfoIterator::new(value.ticker, value.timeframe, value.period)
-    }
+/// Period of the files to fetch, or a command working on downloaded files.
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(flatten)]
+    Period(Period),
+    /// Build tick, volume, dollar or imbalance bars from downloaded trades or aggTrades archives
+    Bars(build_bars::BarsArgs),
+    /// Rebuild 1m klines from downloaded aggTrades and compare them with the downloaded klines
+    Reconcile(reconcile::ReconcileArgs),
+    /// Join the downloaded klines of all tickers on open time into a table of closes and volumes
+    Panel(panel::PanelArgs),
+    /// Join funding rates and mark and index closes onto the downloaded futures klines of all tickers
+    Enrich(enrich::EnrichArgs),
+    /// Query the order book depth at a time or export the liquidity near the mid price from downloaded futures bookDepth files
+    Depth(depth::DepthArgs),
+    /// Print the downloaded klines of a ticker as a table, csv or json
+    Query(query::QueryArgs),
+    /// Summarize the downloaded klines of each ticker and timeframe to sanity check a dataset
+    Stats(stats::StatsArgs),
+    /// Serve the downloaded klines over http in the shape of the binance api
+    Serve(serve::ServeArgs),
 }
 
-type FileProgressReciever = mpsc::UnboundedReceiver<Msg>;
+impl Input {
+    /// Period of the fetched file.
+    fn period(&self) -> Option<&Period> {
+        match &self.command {
+            Some(Command::Period(period)) => Some(period),
+            _ => None,
+        }
+    }
 
-fn download_files(
-    fileinfo_iter: impl Iterator<Item = FileInfo>,
-    overwrite: bool,
-) -> FileProgressReciever {
-    let (tx, file_progress_rx) = mpsc::unbounded_channel::<Msg>();
-
-    let client = reqwest::Client::new();
-
-    for fileinfo in fileinfo_iter {
-        tokio::spawn(crate::fetch::download_file(
-            fileinfo,
-            client.clone(),
-            tx.clone(),
-            overwrite,
-        ));
-    }
-
-    file_progress_rx
-}
-
-async fn handle_file_updates(
-    mut rx: FileProgressReciever,
-    progress_bars: bool,
-) -> Result<(), Vec<(FileInfo, Error)>> {
-    let mut errors = Vec::new();
-
-    if progress_bars {
-        let mut bars = ProgressBars::new();
-        while let Some(Msg { file_id, msg_type }) = rx.recv().await {
-            match msg_type {
-                MsgType::Written { bytes } => bars.increment(file_id, bytes),
-                MsgType::Done => bars.finish(file_id, None::<String>),
-                MsgType::Starting { total_size, name } => bars.new_bar(file_id, name, total_size),
-                MsgType::Error { error, fileinfo } => {
-                    errors.push((fileinfo, error));
-                    bars.abandon(file_id, Some("ERROR"));
-                }
-            }
+    fn output(&self) -> Output {
+        if self.json {
+            Output::Json
+        } else if self.quiet {
+            Output::Quiet
+        } else {
+            self.reporter
         }
-    } else {
-        while let Some(Msg { msg_type, .. }) = rx.recv().await {
-            if let MsgType::Error { fileinfo, error } = msg_type {
-                errors.push((fileinfo, error));
-            }
+    }
+
+    /// The hosts to download from, arguments take precedence over the config file.
+    fn hosts(&self, config: &Config) -> Result<Vec<Url>, ConfigError> {
+        let base_url = match &self.base_url {
+            Some(base_url) => base_url.clone(),
+            None => config
+                .base_url()?
+                .unwrap_or_else(|| parse_base_url(DEFAULT_BASE_URL).expect("valid default url")),
+        };
+
+        let mirrors = if self.mirrors.is_empty() {
+            config.mirrors()?
+        } else {
+            self.mirrors.clone()
+        };
+
+        Ok(std::iter::once(base_url).chain(mirrors).collect())
+    }
+
+    /// Options for the http client, arguments take precedence over the config file.
+    fn client_options(&self, config: &Config) -> Result<ClientOptions, ConfigError> {
+        let config = config.client_options()?;
+        Ok(ClientOptions {
+            proxy: self.proxy.clone().or(config.proxy),
+            ca_bundle: self.ca_bundle.clone().or(config.ca_bundle),
+            connect_timeout: self
+                .connect_timeout
+                .map(Duration::from_secs)
+                .or(config.connect_timeout),
+            read_timeout: self
+                .read_timeout
+                .map(Duration::from_secs)
+                .or(config.read_timeout),
+            user_agent: self.user_agent.clone().or(config.user_agent),
+        })
+    }
+}
+
+/// How progress of the downloads is shown to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Output {
+    /// Progress bar for each file
+    Bars,
+    /// A line when each file starts and ends
+    Log,
+    /// One json event per line
+    Json,
+    /// Only errors
+    Quiet,
+}
+
+impl Output {
+    fn reporter(self, total_files: usize) -> Box<dyn Reporter> {
+        match self {
+            Output::Bars => Box::new(ProgressBars::new(total_files)),
+            Output::Log => Box::new(log::Log::default()),
+            Output::Json => Box::new(events::JsonEvents::default()),
+            Output::Quiet => Box::new(Silent),
         }
     }
+}
 
-    if !errors.is_empty() {
-        return Err(errors);
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn exit_code_of_downloads() {
+        assert_eq!(exit_code::SUCCESS, exit_code::of_downloads(3, 0, 0));
+        // files that already exist are skipped, not failed
+        assert_eq!(exit_code::SUCCESS, exit_code::of_downloads(3, 0, 3));
+        assert_eq!(exit_code::SUCCESS, exit_code::of_downloads(3, 0, 1));
+        assert_eq!(exit_code::PARTIAL_FAILURE, exit_code::of_downloads(3, 1, 0));
+        assert_eq!(exit_code::PARTIAL_FAILURE, exit_code::of_downloads(4, 1, 2));
+        assert_eq!(exit_code::TOTAL_FAILURE, exit_code::of_downloads(3, 3, 0));
+        assert_eq!(exit_code::TOTAL_FAILURE, exit_code::of_downloads(3, 2, 1));
+    }
 }
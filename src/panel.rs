@@ -0,0 +1,76 @@
+use crate::downloaded::{read_downloaded, skipped};
+use crate::ticker_args::{read_required_tickers, TickerArg};
+use crate::time_args::TimeArgs;
+
+use cryptory::{to_datetime, FileInfoIterator, Fill, Kline, Market, Panel, Period, TimeFrame};
+
+use clap::Args;
+
+use std::path::PathBuf;
+
+/// Join the downloaded klines of all tickers into one table.
+#[derive(Debug, Args)]
+pub struct PanelArgs {
+    /// Tickers to join, use @FILE or - to read them from a file or stdin
+    #[arg(value_name = "TICKER", required = true)]
+    tickers: Vec<TickerArg>,
+
+    /// Timeframe of the klines
+    #[arg(short, long)]
+    timeframe: TimeFrame,
+
+    /// Market the klines were downloaded from
+    #[arg(long, value_enum, default_value_t = Market::Spot)]
+    market: Market,
+
+    /// How bars missing for a ticker are filled
+    #[arg(long, value_enum, default_value_t = Fill::Null)]
+    fill: Fill,
+
+    /// Directory with the downloaded files
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Write the panel to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    time: TimeArgs,
+
+    /// Period of the files to join, its days are in the timezone of --timezone
+    #[command(subcommand)]
+    period: Period,
+}
+
+/// Write a csv with a row per open time and the close and volume of each ticker.
+/// Files that were not downloaded are skipped, e.g. days before a ticker was listed.
+pub fn export_panel(args: &PanelArgs) -> Result<(), String> {
+    let tickers = read_required_tickers(&args.tickers)?;
+    args.period.check_order().map_err(|e| e.to_string())?;
+
+    let period = args.period.in_timezone(args.time.timezone);
+    let range = args.period.time_range(args.time.timezone);
+    let mut series = Vec::new();
+    for ticker in tickers {
+        let files =
+            FileInfoIterator::new(vec![ticker.clone()], vec![args.timeframe], period.clone())
+                .with_market(args.market)
+                .with_output_dir(args.dir.clone());
+        let mut klines: Vec<Kline> = read_downloaded(files, Kline::read, skipped)?
+            .into_iter()
+            .flatten()
+            .collect();
+        klines.retain(|kline| range.contains(&to_datetime(kline.open_time)));
+        series.push((ticker.to_string(), klines));
+    }
+
+    let panel = Panel::new(series, args.fill);
+    let time = args.time.output();
+    let result = match &args.output {
+        Some(path) => std::fs::File::create(path)
+            .and_then(|file| panel.write_csv_with(std::io::BufWriter::new(file), &time)),
+        None => panel.write_csv_with(std::io::BufWriter::new(std::io::stdout().lock()), &time),
+    };
+    result.map_err(|e| format!("could not write panel: {e}"))
+}
@@ -1,26 +1,66 @@
-#![allow(unused, unreachable_code)]
-use std::{borrow::Cow, collections::HashMap};
+use std::collections::HashMap;
 
+use cryptory::{Msg, MsgType, Reporter};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
 pub struct ProgressBars {
     current_bars: HashMap<usize, (String, ProgressBar)>,
     style: ProgressStyle,
     multi_progress: MultiProgress,
+    overall: Overall,
 }
 
-impl ProgressBars {
-    pub fn new() -> Self {
-        let style =
-            ProgressStyle::with_template("{msg} {bar:30} {decimal_bytes:>7}/{decimal_total_bytes}")
-                .unwrap()
-                .progress_chars("##-");
+/// Top bar tracking all files together.
+/// The total size is only known for files that have started,
+/// so it is estimated from the average size of those.
+struct Overall {
+    bar: ProgressBar,
+    total_files: usize,
+    started: usize,
+    finished: usize,
+    failed: usize,
+    /// Files that failed before they started.
+    never_started: usize,
+    /// Sum of the sizes of started files that reported a size.
+    known_size: u64,
+    files_with_known_size: usize,
+}
 
-        Self {
+impl ProgressBars {
+    pub fn new(total_files: usize) -> Self {
+        let style = ProgressStyle::with_template(
+            "{msg} {bar:30} {decimal_bytes:>7}/{decimal_total_bytes} {decimal_bytes_per_sec:>10}",
+        )
+        .unwrap()
+        .progress_chars("##-");
+
+        let overall_style = ProgressStyle::with_template(
+            "{msg} [{elapsed_precise}] {bar:30} {decimal_bytes:>7}/~{decimal_total_bytes} {decimal_bytes_per_sec:>10} ETA {eta}",
+        )
+        .expect("expect correct template")
+        .progress_chars("##-");
+
+        let multi_progress = MultiProgress::default();
+        let bar = multi_progress.add(ProgressBar::new(0).with_style(overall_style));
+        bar.enable_steady_tick(std::time::Duration::from_millis(500));
+
+        let mut bars = Self {
             current_bars: HashMap::default(),
-            multi_progress: MultiProgress::default(),
+            multi_progress,
             style,
-        }
+            overall: Overall {
+                bar,
+                total_files,
+                started: 0,
+                finished: 0,
+                failed: 0,
+                never_started: 0,
+                known_size: 0,
+                files_with_known_size: 0,
+            },
+        };
+        bars.overall.update();
+        bars
     }
 
     pub fn new_bar(&mut self, bar_id: usize, name: String, total_size: Option<u64>) {
@@ -35,7 +75,7 @@ impl ProgressBars {
         } else {
             let spinner = ProgressBar::new_spinner()
                 .with_style(
-                    ProgressStyle::with_template("{msg} {spinner} {decimal_bytes:>7}/{decimal_total_bytes}")
+                    ProgressStyle::with_template("{msg} {spinner} {decimal_bytes:>7}/{decimal_total_bytes} {decimal_bytes_per_sec:>10}")
                         .expect("expect correct template"),
                 )
                 .with_message(name.to_string());
@@ -45,6 +85,13 @@ impl ProgressBars {
 
         let pb = self.multi_progress.add(pb);
         self.current_bars.insert(bar_id, (name, pb));
+
+        self.overall.started += 1;
+        if let Some(total_size) = total_size {
+            self.overall.known_size += total_size;
+            self.overall.files_with_known_size += 1;
+        }
+        self.overall.update();
     }
 
     pub fn increment(&mut self, bar_id: usize, with: u64) {
@@ -53,32 +100,37 @@ impl ProgressBars {
             .expect("Internal error: expected to find progressbar");
 
         bar.inc(with);
+        self.overall.bar.inc(with);
+    }
 
-        if bar.should_finish() {
-            self.finish(bar_id, Some("Done"));
-        }
+    /// Remove the bar of a finished file, so only active files stay on screen.
+    pub fn finish_bar(&mut self, bar_id: usize) {
+        self.remove_bar(bar_id);
+        self.overall.finished += 1;
+        self.overall.update();
     }
 
-    pub fn finish(&mut self, bar_id: usize, msg: Option<impl ToString>) {
-        let bar = self
-            .get_mut_bar(bar_id)
-            .expect("Internal error: expected to find progressbar");
-        match msg {
-            Some(msg) => bar.finish_with_message(msg.to_string()),
-            None => bar.finish(),
+    /// Remove the bar of a failed file.
+    /// Files can fail before they start, so the bar may not exist.
+    pub fn abandon(&mut self, bar_id: usize) {
+        match self.remove_bar(bar_id) {
+            Some(bar) => {
+                // the rest of the file will never be downloaded
+                if let Some(length) = bar.length() {
+                    self.overall.known_size -= length.saturating_sub(bar.position());
+                }
+            }
+            None => self.overall.never_started += 1,
         }
+        self.overall.failed += 1;
+        self.overall.update();
     }
-    /// Abondon a bar with an optional message.
-    /// Does nothing if bar is not found
-    pub fn abandon(&mut self, bar_id: usize, msg: Option<impl ToString>) {
-        let Ok(bar) = self.get_mut_bar(bar_id) else {
-            return;
-        };
 
-        match msg {
-            Some(msg) => bar.abandon_with_message(msg.to_string()),
-            None => bar.abandon(),
-        }
+    fn remove_bar(&mut self, bar_id: usize) -> Option<ProgressBar> {
+        let (_, bar) = self.current_bars.remove(&bar_id)?;
+        bar.finish_and_clear();
+        self.multi_progress.remove(&bar);
+        Some(bar)
     }
 
     fn get_mut_bar(&mut self, bar_id: usize) -> Result<&mut ProgressBar, ProgressBarsError> {
@@ -91,12 +143,45 @@ impl ProgressBars {
     }
 }
 
-trait ProgressBarHelper {
-    fn should_finish(&self) -> bool;
+impl Overall {
+    fn update(&mut self) {
+        let not_started = self
+            .total_files
+            .saturating_sub(self.started + self.never_started) as u64;
+        let average_size = self
+            .known_size
+            .checked_div(self.files_with_known_size as u64)
+            .unwrap_or_default();
+        self.bar
+            .set_length(self.known_size + average_size * not_started);
+
+        let failed = match self.failed {
+            0 => String::new(),
+            failed => format!(" ({failed} failed)"),
+        };
+        self.bar.set_message(format!(
+            "{}/{} files{failed}",
+            self.finished + self.failed,
+            self.total_files,
+        ));
+    }
 }
-impl ProgressBarHelper for ProgressBar {
-    fn should_finish(&self) -> bool {
-        self.position() >= self.length().unwrap_or(u64::MAX)
+
+impl Reporter for ProgressBars {
+    fn report(&mut self, msg: &Msg) {
+        let file_id = msg.file_id;
+        match &msg.msg_type {
+            MsgType::Written { bytes } => self.increment(file_id, *bytes),
+            MsgType::Done => self.finish_bar(file_id),
+            MsgType::Starting { total_size, name } => {
+                self.new_bar(file_id, name.clone(), *total_size)
+            }
+            MsgType::Error { .. } => self.abandon(file_id),
+        }
+    }
+
+    fn finish(&mut self) {
+        self.overall.bar.finish();
     }
 }
 
@@ -109,20 +194,3 @@ impl std::fmt::Display for ProgressBarsError {
         write!(f, "{:?}", self)
     }
 }
-
-fn expect_file_id(
-    bars: &HashMap<usize, (String, Option<ProgressBar>)>,
-    file_id: usize,
-) -> &(String, Option<ProgressBar>) {
-    bars.get(&file_id).expect("expect file id")
-}
-
-fn expect_progress_bar(
-    bars: &HashMap<usize, (String, Option<ProgressBar>)>,
-    file_id: usize,
-) -> &ProgressBar {
-    expect_file_id(bars, file_id)
-        .1
-        .as_ref()
-        .expect("we expect a bar to be present")
-}
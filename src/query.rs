@@ -0,0 +1,245 @@
+use crate::downloaded::{read_downloaded, skipped};
+use crate::time_args::TimeArgs;
+
+use cryptory::{
+    parse_end_date, parse_start_date, to_datetime, FileInfoIterator, Kline, Market, Period,
+    PeriodName, Ticker, TimeFormat, TimeFrame, TimeOutput,
+};
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeDelta, Utc};
+use clap::{Args, ValueEnum};
+
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Print the downloaded klines of a ticker without extracting the archives.
+#[derive(Debug, Args)]
+pub struct QueryArgs {
+    ticker: Ticker,
+
+    /// Timeframe of the klines
+    #[arg(short, long)]
+    timeframe: TimeFrame,
+
+    /// First day to print, days are in the timezone of --timezone
+    #[arg(long, value_parser = parse_start_date, allow_hyphen_values = true)]
+    from: NaiveDate,
+
+    /// Last day to print [default: the from date]
+    #[arg(long, value_parser = parse_end_date, allow_hyphen_values = true)]
+    to: Option<NaiveDate>,
+
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Market the klines were downloaded from
+    #[arg(long, value_enum, default_value_t = Market::Spot)]
+    market: Market,
+
+    /// Directory with the downloaded files
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    #[command(flatten)]
+    time: TimeArgs,
+}
+
+/// How the klines are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Aligned columns with the open time in the timezone of --timezone
+    Table,
+    /// Same columns as binance kline files
+    Csv,
+    /// One json object per line
+    Json,
+}
+
+/// Print the klines that open between the from and to dates.
+pub fn run_query(args: &QueryArgs) -> Result<(), String> {
+    let to = args.to.unwrap_or(args.from);
+    if to < args.from {
+        return Err(format!("end date {to} is before start date {}", args.from));
+    }
+
+    let files = KlineFiles {
+        dir: args.dir.clone(),
+        market: args.market,
+        ticker: args.ticker.clone(),
+        timeframe: args.timeframe,
+    };
+    let range = Period::new(args.from, Some(to), PeriodName::Daily).time_range(args.time.timezone);
+    let klines = files.read(range, skipped)?;
+
+    let out = std::io::BufWriter::new(std::io::stdout().lock());
+    write_klines(&klines, args.format, &args.time.output(), out)
+        .map_err(|e| format!("could not write klines: {e}"))
+}
+
+/// The downloaded kline files of a ticker and timeframe.
+#[derive(Debug, Clone)]
+pub struct KlineFiles {
+    pub dir: PathBuf,
+    pub market: Market,
+    pub ticker: Ticker,
+    pub timeframe: TimeFrame,
+}
+
+impl KlineFiles {
+    /// The klines that open in the range of times, in order of time.
+    ///
+    /// Files are looked up by the names cryptory downloads them with, a monthly file is used
+    /// when it was downloaded and daily files otherwise.
+    /// Daily files that were not downloaded are skipped and passed to `missing`.
+    pub fn read(
+        &self,
+        range: Range<DateTime<Utc>>,
+        mut missing: impl FnMut(&Path),
+    ) -> Result<Vec<Kline>, String> {
+        // days of the files in UTC
+        let from = range.start.date_naive();
+        let to = (range.end - TimeDelta::milliseconds(1)).date_naive();
+        let mut klines = Vec::new();
+        let mut month = from.with_day(1).expect("1st is valid date");
+        while month <= to {
+            let next_month = month + Months::new(1);
+            let monthly = Period::new(month, None, PeriodName::Monthly);
+            let daily = Period::new(
+                month.max(from),
+                Some(to.min(next_month - TimeDelta::days(1))),
+                PeriodName::Daily,
+            );
+            match self.read_period(monthly, &mut |_| ())? {
+                Some(monthly_klines) => klines.extend(monthly_klines),
+                None => klines.extend(self.read_period(daily, &mut missing)?.unwrap_or_default()),
+            }
+            month = next_month;
+        }
+        klines.retain(|kline| range.contains(&to_datetime(kline.open_time)));
+        Ok(klines)
+    }
+
+    /// The klines of the files of a period, None if none of them were downloaded.
+    fn read_period(
+        &self,
+        period: Period,
+        missing: &mut impl FnMut(&Path),
+    ) -> Result<Option<Vec<Kline>>, String> {
+        let files = FileInfoIterator::new(vec![self.ticker.clone()], vec![self.timeframe], period)
+            .with_market(self.market)
+            .with_output_dir(self.dir.clone());
+        let klines = read_downloaded(files, Kline::read, missing)?;
+        Ok((!klines.is_empty()).then(|| klines.into_iter().flatten().collect()))
+    }
+}
+
+fn write_klines(
+    klines: &[Kline],
+    format: Format,
+    time: &TimeOutput,
+    mut out: impl Write,
+) -> std::io::Result<()> {
+    match format {
+        Format::Table => {
+            writeln!(
+                out,
+                "{:<25} {:>16} {:>16} {:>16} {:>16} {:>20} {:>10}",
+                "open_time", "open", "high", "low", "close", "volume", "count"
+            )?;
+            for kline in klines {
+                writeln!(
+                    out,
+                    "{:<25} {:>16.8} {:>16.8} {:>16.8} {:>16.8} {:>20.8} {:>10}",
+                    to_datetime(kline.open_time)
+                        .with_timezone(&time.timezone)
+                        .format("%Y-%m-%d %H:%M:%S %:z"),
+                    kline.open,
+                    kline.high,
+                    kline.low,
+                    kline.close,
+                    kline.volume,
+                    kline.trades
+                )?;
+            }
+        }
+        Format::Csv => {
+            writeln!(out, "{}", Kline::CSV_HEADER)?;
+            for kline in klines {
+                writeln!(out, "{}", kline.to_csv_with(time))?;
+            }
+        }
+        Format::Json => {
+            for kline in klines {
+                let mut json = serde_json::to_value(kline).expect("Kline is valid json");
+                if time.format == TimeFormat::Iso {
+                    json["open_time"] = time.format(kline.open_time).into();
+                    json["close_time"] = time.format(kline.close_time).into();
+                }
+                writeln!(out, "{json}")?;
+            }
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use crate::test_klines::{kline_at, HOUR, START};
+
+    /// Hours from 2025-01-01 to 2025-01-30.
+    const JAN_30: i64 = 29 * 24;
+
+    /// A kline archive with hourly klines from `hours` after 2025-01-30, closing at `close`.
+    fn write_archive(dir: &Path, name: &str, hours: Range<i64>, close: f64) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(dir.join(name)).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        let csv_name = name.replace(".zip", ".csv");
+        zip.start_file(csv_name, options).unwrap();
+        for hour in hours {
+            let kline = Kline {
+                close,
+                ..kline_at(JAN_30 + hour)
+            };
+            writeln!(zip, "{}", kline.to_csv()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn read_monthly_or_daily_files() {
+        let dir = std::env::temp_dir().join(format!("cryptory-query-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // january has a monthly file, the daily file of the 31st is not used
+        write_archive(&dir, "BTCUSDT-1h-2025-01.zip", 0..48, 1.0);
+        write_archive(&dir, "BTCUSDT-1h-2025-01-31.zip", 24..48, 2.0);
+        // february only has daily files, the 2nd was not downloaded
+        write_archive(&dir, "BTCUSDT-1h-2025-02-01.zip", 48..72, 3.0);
+
+        let files = KlineFiles {
+            dir: dir.clone(),
+            market: Market::Spot,
+            ticker: "btcusdt".parse().unwrap(),
+            timeframe: TimeFrame::H1,
+        };
+        let hour = |hours: i64| to_datetime(START + (JAN_30 + hours) * HOUR);
+        let range = hour(36)..hour(73);
+        let mut missing = Vec::new();
+        let klines = files.read(range, |path| missing.push(path.to_path_buf()));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let klines = klines.unwrap();
+        let open_hours: Vec<i64> = klines
+            .iter()
+            .map(|kline| (kline.open_time - START) / HOUR - JAN_30)
+            .collect();
+        assert_eq!((36..72).collect::<Vec<_>>(), open_hours);
+        assert!(klines[..12].iter().all(|kline| kline.close == 1.0));
+        assert!(klines[12..].iter().all(|kline| kline.close == 3.0));
+        assert_eq!(vec![dir.join("BTCUSDT-1h-2025-02-02.zip")], missing);
+    }
+}
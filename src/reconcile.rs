@@ -0,0 +1,99 @@
+use cryptory::{
+    parse_start_date, reconcile, to_datetime, BarBuilder, BarKind, DataType, FileInfoIterator,
+    Kline, Market, Mismatch, Period, PeriodName, Ticker, TimeFrame, Trade,
+};
+
+use chrono::NaiveDate;
+use clap::Args;
+
+use std::path::{Path, PathBuf};
+
+/// Rebuild 1m klines from downloaded aggTrades and compare them with the downloaded klines.
+#[derive(Debug, Args)]
+pub struct ReconcileArgs {
+    ticker: Ticker,
+
+    /// Day to compare, the 1m kline and aggTrades files of this day have to be downloaded
+    #[arg(value_parser = parse_start_date, allow_hyphen_values = true)]
+    date: NaiveDate,
+
+    /// Market the klines and aggTrades were downloaded from
+    #[arg(long, value_enum, default_value_t = Market::Spot)]
+    market: Market,
+
+    /// Directory with the downloaded files
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Largest difference relative to the kline that is not reported
+    #[arg(long, default_value_t = 1e-8)]
+    tolerance: f64,
+}
+
+/// Print the bars that differ, returns whether all bars matched.
+pub fn run_reconcile(args: &ReconcileArgs) -> Result<bool, String> {
+    let klines_path = file_path(args, DataType::Klines);
+    let trades_path = file_path(args, DataType::AggTrades);
+
+    let klines = Kline::read(&klines_path).map_err(|e| read_error(&klines_path, e))?;
+    let rebuilt = rebuild(&trades_path).map_err(|e| read_error(&trades_path, e))?;
+
+    let mismatches = reconcile(&klines, &rebuilt, args.tolerance);
+    for mismatch in &mismatches {
+        let open_time = to_datetime(mismatch.open_time());
+        match mismatch {
+            Mismatch::MissingKline { rebuilt } => println!(
+                "{open_time} missing kline, trades have {} trades and volume {:.8}",
+                rebuilt.trades, rebuilt.volume
+            ),
+            Mismatch::MissingTrades { kline } => println!(
+                "{open_time} missing trades, kline has {} trades and volume {:.8}",
+                kline.trades, kline.volume
+            ),
+            Mismatch::Different { fields, .. } => {
+                for field in fields {
+                    println!(
+                        "{open_time} {}: kline {:.8} trades {:.8} (diff {:+.8}, {:+.4}%)",
+                        field.field,
+                        field.kline,
+                        field.rebuilt,
+                        field.diff(),
+                        field.relative_diff() * 100.0
+                    );
+                }
+            }
+        }
+    }
+
+    println!(
+        "\n{} klines compared with {} bars rebuilt from trades, {} mismatching.",
+        klines.len(),
+        rebuilt.len(),
+        mismatches.len()
+    );
+    Ok(mismatches.is_empty())
+}
+
+/// Path of the file of the day as downloaded by cryptory.
+fn file_path(args: &ReconcileArgs, data_type: DataType) -> PathBuf {
+    let period = Period::new(args.date, None, PeriodName::Daily);
+    FileInfoIterator::new(vec![args.ticker.clone()], vec![TimeFrame::M1], period)
+        .with_market(args.market)
+        .with_data_type(data_type)
+        .with_output_dir(args.dir.clone())
+        .next()
+        .expect("a day has a file")
+        .file_path
+}
+
+fn rebuild(path: &Path) -> Result<Vec<Kline>, cryptory::DataError> {
+    let mut builder = BarBuilder::new(BarKind::Time(TimeFrame::M1));
+    let mut bars = Vec::new();
+    Trade::read(path, |trade| bars.extend(builder.push(&trade)))?;
+    bars.extend(builder.finish());
+    Ok(bars)
+}
+
+fn read_error(path: &Path, e: cryptory::DataError) -> String {
+    format!("{}: {e}", path.display())
+}
@@ -0,0 +1,47 @@
+use crate::{Error, FileInfo, Msg, MsgType, Progress};
+
+/// Receives every update from the files being downloaded,
+/// implement this to show the progress in your own way.
+pub trait Reporter {
+    fn report(&mut self, msg: &Msg);
+
+    /// Called once when all files are finished.
+    fn finish(&mut self) {}
+}
+
+/// Reporter that ignores all updates.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Silent;
+
+impl Reporter for Silent {
+    fn report(&mut self, _msg: &Msg) {}
+}
+
+/// Outcome of a batch of downloads.
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// Files that were downloaded successfully.
+    pub finished: usize,
+    /// Files that failed, and why.
+    pub errors: Vec<(FileInfo, Error)>,
+}
+
+impl Progress {
+    /// Pass every update to the reporter until all files are finished.
+    pub async fn report_to(mut self, reporter: &mut dyn Reporter) -> Summary {
+        let mut summary = Summary::default();
+
+        while let Some(msg) = self.recv().await {
+            reporter.report(&msg);
+
+            match msg.msg_type {
+                MsgType::Done => summary.finished += 1,
+                MsgType::Error { fileinfo, error } => summary.errors.push((fileinfo, error)),
+                MsgType::Starting { .. } | MsgType::Written { .. } => (),
+            }
+        }
+
+        reporter.finish();
+        summary
+    }
+}
@@ -0,0 +1,66 @@
+use crate::FileInfo;
+
+use serde::{Deserialize, Serialize};
+
+use std::path::Path;
+
+/// Files that were not downloaded when a job was interrupted,
+/// saved so a later run can pick them up.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResumeFile {
+    pub files: Vec<FileInfo>,
+}
+
+impl ResumeFile {
+    pub fn new(files: Vec<FileInfo>) -> Self {
+        Self { files }
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> std::io::Result<ResumeFile> {
+        let content = std::fs::read(path)?;
+        serde_json::from_slice(&content)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let content = serde_json::to_vec_pretty(self).expect("ResumeFile is valid json");
+        std::fs::write(path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reqwest::Url;
+
+    #[test]
+    fn write_and_read_back() {
+        let url_path = "data/spot/daily/klines/BTCUSDT/1h/BTCUSDT-1h-2025-01-01.zip";
+        let file = FileInfo {
+            source_url: Url::parse("https://data.binance.vision/")
+                .unwrap()
+                .join(url_path)
+                .unwrap(),
+            mirror_urls: vec![Url::parse("https://mirror.internal/")
+                .unwrap()
+                .join(url_path)
+                .unwrap()],
+            file_path: "data/BTCUSDT-1h-2025-01-01.zip".into(),
+            file_id: 3,
+        };
+        let path =
+            std::env::temp_dir().join(format!("cryptory-resume-{}.json", std::process::id()));
+
+        ResumeFile::new(vec![file.clone()]).write(&path).unwrap();
+        let read = ResumeFile::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let files = read.unwrap().files;
+        assert_eq!(1, files.len());
+        assert_eq!(file.source_url, files[0].source_url);
+        assert_eq!(file.mirror_urls, files[0].mirror_urls);
+        assert_eq!(file.file_path, files[0].file_path);
+        assert_eq!(file.file_id, files[0].file_id);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::query::KlineFiles;
+
+use cryptory::{to_datetime, Kline, Market, Ticker, TimeFrame};
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::Args;
+use serde_json::{json, Value};
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Serve the downloaded klines over http in the shape of the binance api.
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: SocketAddr,
+
+    /// Directory with the downloaded files
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Market the klines were downloaded from
+    #[arg(long, value_enum, default_value_t = Market::Spot)]
+    market: Market,
+}
+
+/// Klines returned when no limit is given, same as binance.
+const DEFAULT_LIMIT: usize = 500;
+const MAX_LIMIT: usize = 1000;
+
+/// Binance publishes data from 2017 on, times outside 2017-01-01 up to 2100-01-01 are rejected
+/// instead of looking for the files of every month up to them.
+const EARLIEST_TIME: i64 = 1_483_228_800_000;
+const LATEST_TIME: i64 = 4_102_444_800_000;
+
+/// Serve `/klines` and `/api/v3/klines` until Ctrl-C is pressed.
+pub async fn serve(args: &ServeArgs) -> Result<(), String> {
+    let state = ServeState {
+        dir: args.dir.clone(),
+        market: args.market,
+    };
+    let app = Router::new()
+        .route("/klines", get(klines))
+        .route("/api/v3/klines", get(klines))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(args.addr)
+        .await
+        .map_err(|e| format!("could not listen on {}: {e}", args.addr))?;
+    eprintln!(
+        "Serving klines from {} on http://{}/klines",
+        args.dir.display(),
+        args.addr
+    );
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| format!("server stopped: {e}"))
+}
+
+#[derive(Debug, Clone)]
+struct ServeState {
+    dir: PathBuf,
+    market: Market,
+}
+
+/// An error in the shape binance returns them.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    code: i32,
+    msg: String,
+}
+
+impl ApiError {
+    fn bad_request(code: i32, msg: impl Into<String>) -> ApiError {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            code,
+            msg: msg.into(),
+        }
+    }
+
+    /// A parameter that is required is missing.
+    fn missing(param: &str) -> ApiError {
+        ApiError::bad_request(
+            -1102,
+            format!("Mandatory parameter '{param}' was not sent, was empty/null, or malformed."),
+        )
+    }
+
+    fn invalid(param: &str) -> ApiError {
+        ApiError::bad_request(
+            -1130,
+            format!("Data sent for parameter '{param}' is not valid."),
+        )
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({ "code": self.code, "msg": self.msg });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Query of `/klines`, named like the binance api.
+/// `start` and `end` can be used for `startTime` and `endTime`, both in milliseconds.
+#[derive(Debug)]
+struct KlinesQuery {
+    ticker: Ticker,
+    timeframe: TimeFrame,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    limit: usize,
+}
+
+impl KlinesQuery {
+    fn parse(params: &HashMap<String, String>) -> Result<KlinesQuery, ApiError> {
+        let param = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| params.get(*name))
+                .filter(|value| !value.is_empty())
+        };
+        let time = |names: &[&str]| -> Result<Option<DateTime<Utc>>, ApiError> {
+            param(names)
+                .map(|value| {
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|ms| (EARLIEST_TIME..LATEST_TIME).contains(ms))
+                        .and_then(DateTime::from_timestamp_millis)
+                        .ok_or_else(|| ApiError::invalid(names[0]))
+                })
+                .transpose()
+        };
+
+        let symbol = param(&["symbol"]).ok_or_else(|| ApiError::missing("symbol"))?;
+        // the symbol is part of the file path
+        let ticker = Some(symbol)
+            .filter(|symbol| symbol.chars().all(|c| c.is_ascii_alphanumeric()))
+            .and_then(|symbol| symbol.parse().ok())
+            .ok_or_else(|| ApiError::bad_request(-1121, "Invalid symbol."))?;
+        // timeframes are parsed case insensitive, binance uses 1M for a month and 1m for a minute
+        let timeframe = match param(&["interval"]).map(String::as_str) {
+            Some("1M") => Ok(TimeFrame::Mo1),
+            Some(interval) => interval.parse(),
+            None => return Err(ApiError::missing("interval")),
+        }
+        .map_err(|_| ApiError::bad_request(-1120, "Invalid interval."))?;
+        let limit = match param(&["limit"]) {
+            Some(limit) => limit
+                .parse::<usize>()
+                .ok()
+                .filter(|limit| *limit > 0)
+                .ok_or_else(|| ApiError::invalid("limit"))?
+                .min(MAX_LIMIT),
+            None => DEFAULT_LIMIT,
+        };
+
+        Ok(KlinesQuery {
+            ticker,
+            timeframe,
+            start: time(&["startTime", "start"])?,
+            end: time(&["endTime", "end"])?,
+            limit,
+        })
+    }
+
+    /// The times of the klines to read, at most `limit` bars from the start time,
+    /// or up to the end time when there is no start time. The end time is included like at binance.
+    fn window(&self, now: DateTime<Utc>) -> Result<Range<DateTime<Utc>>, ApiError> {
+        // the time the limit of bars can span, a month is at most 31 days
+        let bar = self.timeframe.duration().unwrap_or(TimeDelta::days(31));
+        let span = bar * self.limit as i32;
+        let after = |end: DateTime<Utc>| end + TimeDelta::milliseconds(1);
+
+        let range = match (self.start, self.end) {
+            (Some(start), Some(end)) if end < start => return Err(ApiError::invalid("endTime")),
+            (Some(start), Some(end)) => start..after(end).min(start + span),
+            (Some(start), None) => start..start + span,
+            (None, end) => {
+                let end = after(end.unwrap_or(now));
+                end - span..end
+            }
+        };
+        Ok(range)
+    }
+}
+
+/// Like binance, the first `limit` klines from the start time are returned,
+/// or the last `limit` klines up to the end time or now when there is no start time.
+async fn klines(
+    State(state): State<ServeState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<Value>>, ApiError> {
+    let query = KlinesQuery::parse(&params)?;
+    let range = query.window(Utc::now())?;
+
+    let files = KlineFiles {
+        dir: state.dir,
+        market: state.market,
+        ticker: query.ticker,
+        timeframe: query.timeframe,
+    };
+    let mut klines = tokio::task::spawn_blocking(move || files.read(range, |_| ()))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|klines| klines)
+        .map_err(|msg| ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            code: -1000,
+            msg,
+        })?;
+
+    if query.start.is_none() && klines.len() > query.limit {
+        klines.drain(..klines.len() - query.limit);
+    }
+    klines.truncate(query.limit);
+
+    Ok(Json(klines.iter().map(to_api_kline).collect()))
+}
+
+/// A kline as binance returns them, times in milliseconds and decimals as strings.
+fn to_api_kline(kline: &Kline) -> Value {
+    json!([
+        to_datetime(kline.open_time).timestamp_millis(),
+        format!("{:.8}", kline.open),
+        format!("{:.8}", kline.high),
+        format!("{:.8}", kline.low),
+        format!("{:.8}", kline.close),
+        format!("{:.8}", kline.volume),
+        to_datetime(kline.close_time).timestamp_millis(),
+        format!("{:.8}", kline.quote_volume),
+        kline.trades,
+        format!("{:.8}", kline.taker_buy_volume),
+        format!("{:.8}", kline.taker_buy_quote_volume),
+        "0"
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_klines::{HOUR, START};
+
+    fn parse(query: &str) -> Result<KlinesQuery, ApiError> {
+        let params = query
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        KlinesQuery::parse(&params)
+    }
+
+    fn error_code(query: &str) -> i32 {
+        parse(query).unwrap_err().code
+    }
+
+    #[test]
+    fn parse_query() {
+        let query = parse("symbol=btcusdt&interval=1h&start=1735689600000&limit=5000").unwrap();
+        assert_eq!("BTCUSDT", query.ticker.to_string());
+        assert_eq!(TimeFrame::H1, query.timeframe);
+        assert_eq!(Some(to_datetime(START)), query.start);
+        assert_eq!(None, query.end);
+        assert_eq!(MAX_LIMIT, query.limit);
+
+        // binance names for a month and a minute
+        let month = parse("symbol=BTCUSDT&interval=1M").unwrap();
+        assert_eq!(TimeFrame::Mo1, month.timeframe);
+        assert_eq!(DEFAULT_LIMIT, month.limit);
+        let minute = parse("symbol=BTCUSDT&interval=1m").unwrap();
+        assert_eq!(TimeFrame::M1, minute.timeframe);
+
+        assert_eq!(-1102, error_code("interval=1h"));
+        assert_eq!(-1102, error_code("symbol=BTCUSDT"));
+        assert_eq!(-1121, error_code("symbol=../../BTCUSDT&interval=1h"));
+        assert_eq!(-1120, error_code("symbol=BTCUSDT&interval=2h30"));
+        assert_eq!(-1130, error_code("symbol=BTCUSDT&interval=1h&limit=0"));
+        assert_eq!(
+            -1130,
+            error_code("symbol=BTCUSDT&interval=1h&startTime=-8000000000000000")
+        );
+        assert_eq!(
+            -1130,
+            error_code("symbol=BTCUSDT&interval=1h&endTime=8000000000000000")
+        );
+    }
+
+    #[test]
+    fn window_of_limit_bars() {
+        let at = |hours: i64| to_datetime(START + hours * HOUR);
+        let window = |query: &str| {
+            parse(&format!("symbol=BTCUSDT&interval=1h&limit=10&{query}"))
+                .unwrap()
+                .window(at(1000))
+        };
+        let ms = TimeDelta::milliseconds(1);
+
+        // the end time is included
+        assert_eq!(
+            at(0)..at(5) + ms,
+            window(&format!("startTime={START}&endTime={}", START + 5 * HOUR)).unwrap()
+        );
+        // limited to 10 bars from the start time
+        assert_eq!(
+            at(0)..at(10),
+            window(&format!("startTime={START}&endTime={}", START + 500 * HOUR)).unwrap()
+        );
+        assert_eq!(
+            at(0)..at(10),
+            window(&format!("startTime={START}")).unwrap()
+        );
+        // the last 10 bars up to the end time or now
+        assert_eq!(
+            at(91) + ms..at(101) + ms,
+            window(&format!("endTime={}", START + 101 * HOUR)).unwrap()
+        );
+        assert_eq!(at(990) + ms..at(1000) + ms, window("").unwrap());
+
+        let end_before_start = window(&format!("startTime={}&endTime={START}", START + HOUR));
+        assert_eq!(-1130, end_before_start.unwrap_err().code);
+    }
+
+    #[test]
+    fn api_kline() {
+        let kline = Kline {
+            open_time: START * 1000,
+            open: 1.5,
+            close_time: (START + HOUR) * 1000 - 1,
+            trades: 3,
+            ..Kline::default()
+        };
+        let json = to_api_kline(&kline);
+        assert_eq!(json!(START), json[0]);
+        assert_eq!(json!("1.50000000"), json[1]);
+        assert_eq!(json!(START + HOUR - 1), json[6]);
+        assert_eq!(json!(3), json[8]);
+        assert_eq!(12, json.as_array().unwrap().len());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::ticker_args::{read_tickers, TickerArg};
+
+use cryptory::{DataType, Kline, KlineStats, Market, TimeFrame};
+
+use clap::Args;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Summarize the downloaded klines of each ticker and timeframe.
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    /// Only summarize these tickers [default: all], use @FILE or - to read them from a file or stdin
+    #[arg(value_name = "TICKER")]
+    tickers: Vec<TickerArg>,
+
+    /// Only summarize these timeframes [default: all], e.g. 1h,1d
+    #[arg(short, long, value_delimiter = ',')]
+    timeframe: Vec<TimeFrame>,
+
+    /// Directory with the downloaded files
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Market the klines were downloaded from
+    #[arg(long, value_enum, default_value_t = Market::Spot)]
+    market: Market,
+}
+
+/// Print the stats of every ticker and timeframe with kline files in the directory,
+/// limited to the tickers and timeframes given.
+pub fn print_stats(args: &StatsArgs) -> Result<(), String> {
+    let tickers = read_tickers(&args.tickers)?;
+    if tickers.is_empty() && !args.tickers.is_empty() {
+        return Err("no tickers were read, leave them out to summarize all".to_string());
+    }
+    let dir = match args.market {
+        Market::Spot => args.dir.clone(),
+        market => args.dir.join(market.dir()).join(DataType::Klines.as_str()),
+    };
+    let entries = std::fs::read_dir(&dir)
+        .map_err(|e| format!("could not read directory {}: {e}", dir.display()))?;
+
+    let mut datasets: BTreeMap<(String, TimeFrame), Vec<PathBuf>> = BTreeMap::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("could not read directory {}: {e}", dir.display()))?
+            .path();
+        let Some((ticker, timeframe)) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_file_name)
+        else {
+            continue;
+        };
+        let selected = (tickers.is_empty() || tickers.iter().any(|t| t.to_string() == ticker))
+            && (args.timeframe.is_empty() || args.timeframe.contains(&timeframe));
+        if selected {
+            datasets.entry((ticker, timeframe)).or_default().push(path);
+        }
+    }
+    if datasets.is_empty() {
+        return Err(format!("no kline files found in {}", dir.display()));
+    }
+
+    for ((ticker, timeframe), files) in datasets {
+        let mut klines = Vec::new();
+        for file in &files {
+            let file_klines = Kline::read(file).map_err(|e| format!("{}: {e}", file.display()))?;
+            klines.extend(file_klines);
+        }
+        print_dataset(
+            &ticker,
+            timeframe,
+            files.len(),
+            &KlineStats::new(&klines, timeframe),
+        );
+    }
+    Ok(())
+}
+
+/// Ticker and timeframe of a kline file downloaded by cryptory,
+/// e.g. BTCUSDT-1h-2025-01-01.zip or BTCUSDT-1h-2025-01.zip.
+fn parse_file_name(file_name: &str) -> Option<(String, TimeFrame)> {
+    let mut parts = file_name.strip_suffix(".zip")?.splitn(3, '-');
+    let ticker = parts.next()?;
+    let timeframe = parts.next()?.parse().ok()?;
+    let date = parts.next()?;
+    date.starts_with(|c: char| c.is_ascii_digit())
+        .then(|| (ticker.to_string(), timeframe))
+}
+
+fn print_dataset(ticker: &str, timeframe: TimeFrame, files: usize, stats: &KlineStats) {
+    println!("{ticker} {timeframe}");
+    match (stats.first_open, stats.last_open) {
+        (Some(first), Some(last)) => println!(
+            "  coverage        {first} to {last}, {} days with data in {files} files",
+            stats.days
+        ),
+        _ => println!("  coverage        no klines in {files} files"),
+    }
+    println!(
+        "  rows            {} ({} duplicates)",
+        stats.rows, stats.duplicates
+    );
+    match stats.gaps.iter().max_by_key(|gap| gap.bars) {
+        Some(largest) => println!(
+            "  missing bars    {} in {} gaps, largest {} bars from {}",
+            stats.missing_bars,
+            stats.gaps.len(),
+            largest.bars,
+            largest.from
+        ),
+        None => println!("  missing bars    0"),
+    }
+    match &stats.daily_returns {
+        Some(returns) => println!(
+            "  daily returns   {} days, mean {:+.4}% std {:.4}%, min {:+.4}% p5 {:+.4}% median {:+.4}% p95 {:+.4}% max {:+.4}%",
+            returns.count,
+            returns.mean * 100.0,
+            returns.std_dev * 100.0,
+            returns.min * 100.0,
+            returns.p5 * 100.0,
+            returns.median * 100.0,
+            returns.p95 * 100.0,
+            returns.max * 100.0
+        ),
+        None => println!("  daily returns   none, needs klines on consecutive days"),
+    }
+    println!("  avg volume      {:.8}", stats.average_volume);
+    println!("  avg quote vol   {:.8}", stats.average_quote_volume);
+    println!("  avg trades      {:.2}", stats.average_trades);
+    println!();
+}
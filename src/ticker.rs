@@ -1,6 +1,7 @@
 use std::collections::VecDeque;
+use std::io::BufRead;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Ticker(String);
 
 impl std::fmt::Display for Ticker {
@@ -22,6 +23,22 @@ pub struct Tickerator {
     origin: VecDeque<Ticker>,
 }
 
+impl Tickerator {
+    /// Read a list of tickers, one per line.
+    /// Everything after a # is a comment, empty lines are skipped.
+    pub fn from_reader(reader: impl BufRead) -> std::io::Result<Self> {
+        let mut tickers = VecDeque::new();
+        for line in reader.lines() {
+            let line = line?;
+            let symbol = line.split('#').next().unwrap_or_default().trim();
+            if !symbol.is_empty() {
+                tickers.push_back(Ticker(symbol.to_uppercase()));
+            }
+        }
+        Ok(Tickerator::from(tickers))
+    }
+}
+
 impl Iterator for Tickerator {
     type Item = Ticker;
 
@@ -40,3 +57,18 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_tickers() {
+        let list = "# universe\nbtcusdt\n\n  ETHUSDT  # majors\n#SOLUSDT\n";
+        let tickers: Vec<String> = Tickerator::from_reader(list.as_bytes())
+            .unwrap()
+            .map(|ticker| ticker.to_string())
+            .collect();
+        assert_eq!(vec!["BTCUSDT", "ETHUSDT"], tickers);
+    }
+}
@@ -0,0 +1,53 @@
+use cryptory::{JobError, Ticker, Tickerator};
+
+use std::path::PathBuf;
+
+/// A ticker given as argument, or a list of tickers to read.
+#[derive(Debug, Clone)]
+pub enum TickerArg {
+    Ticker(Ticker),
+    File(PathBuf),
+    Stdin,
+}
+
+impl std::str::FromStr for TickerArg {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "-" => Ok(TickerArg::Stdin),
+            s if s.starts_with('@') => Ok(TickerArg::File(PathBuf::from(&s[1..]))),
+            s => Ok(TickerArg::Ticker(s.parse()?)),
+        }
+    }
+}
+
+impl TickerArg {
+    fn tickers(&self) -> Result<Tickerator, String> {
+        match self {
+            TickerArg::Ticker(ticker) => Ok(Tickerator::from(vec![ticker.clone()])),
+            TickerArg::File(path) => std::fs::File::open(path)
+                .and_then(|file| Tickerator::from_reader(std::io::BufReader::new(file)))
+                .map_err(|e| format!("could not read tickers from {}: {e}", path.display())),
+            TickerArg::Stdin => Tickerator::from_reader(std::io::stdin().lock())
+                .map_err(|e| format!("could not read tickers from stdin: {e}")),
+        }
+    }
+}
+
+/// All tickers given as arguments, with the lists read.
+pub fn read_tickers(args: &[TickerArg]) -> Result<Vec<Ticker>, String> {
+    let mut tickers = Vec::new();
+    for ticker in args {
+        tickers.extend(ticker.tickers()?);
+    }
+    Ok(tickers)
+}
+
+/// Same as [`read_tickers`], but fails when no tickers were given or the lists were empty.
+pub fn read_required_tickers(args: &[TickerArg]) -> Result<Vec<Ticker>, String> {
+    let tickers = read_tickers(args)?;
+    if tickers.is_empty() {
+        return Err(JobError::NoTickers.to_string());
+    }
+    Ok(tickers)
+}
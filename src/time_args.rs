@@ -0,0 +1,22 @@
+use cryptory::{TimeFormat, TimeOutput};
+
+use chrono_tz::Tz;
+use clap::Args;
+
+/// How exported timestamps are written, and the timezone of the days of a period.
+#[derive(Debug, Clone, Args)]
+pub struct TimeArgs {
+    /// How open and close times are written
+    #[arg(long, value_enum, default_value_t = TimeFormat::Epoch)]
+    pub time_format: TimeFormat,
+
+    /// Timezone of ISO-8601 times and of the days of the period, e.g. Europe/Amsterdam
+    #[arg(long, default_value = "UTC")]
+    pub timezone: Tz,
+}
+
+impl TimeArgs {
+    pub fn output(&self) -> TimeOutput {
+        TimeOutput::new(self.time_format, self.timezone)
+    }
+}